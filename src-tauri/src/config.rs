@@ -0,0 +1,889 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::credentials::{default_credential_sources, CredentialSource};
+use crate::i18n::{tr, tr_fmt, Locale};
+use crate::logging::LogLevel;
+use crate::parental::{is_pin_hash, AgeLimit};
+use crate::sorting::SortMode;
+use crate::DESKTOP_PRODUCT_ID;
+
+/// Same as `identifier` in `tauri.conf.json`; the config has to be readable
+/// before a Tauri app exists (e.g. for `--preview-launch`).
+pub const APP_IDENTIFIER: &str = "io.drova.launcher";
+const CONFIG_FILE_NAME: &str = "launcher.json";
+const DEFAULT_AUTO_REFRESH_SECS: u64 = 300;
+const DEFAULT_LAUNCH_GRACE_SECS: u64 = 15;
+const DEFAULT_API_BASE_URL: &str = "https://services.drova.io";
+const DEFAULT_HTTP_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_IMAGE_CACHE_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Station owner settings kept next to the app in the config directory.
+/// Loaded at startup and whenever the file changes; `DROVA_*` variables
+/// override single values (see `apply_env_overrides`).
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalConfig {
+    /// Launch profiles keyed by product ID.
+    pub profiles: HashMap<String, Vec<LaunchProfile>>,
+    /// Hooks that run around every game launch.
+    pub hooks: HookSet,
+    /// Extra hooks keyed by product ID, run after the global ones.
+    pub product_hooks: HashMap<String, HookSet>,
+    /// How Desktop products are recognized in the station list.
+    pub desktop: DesktopRules,
+    /// Where station credentials are looked up.
+    pub credentials: CredentialsConfig,
+    /// How often the game list is re-fetched in the background; `0`
+    /// turns it off.
+    pub auto_refresh_secs: Option<u64>,
+    /// Age restriction for the games shown and launched on this station.
+    pub parental: ParentalConfig,
+    /// Keeps players from closing the launcher.
+    pub kiosk: KioskConfig,
+    /// Featured games shown first, in this order, by product ID.
+    pub pinned: Vec<String>,
+    /// Language of backend messages and catalog texts; the OS language
+    /// when unset.
+    pub locale: Option<Locale>,
+    /// Card order until the player picks one.
+    pub sort_mode: Option<SortMode>,
+    pub api: ApiConfig,
+    pub image_cache: ImageCacheConfig,
+    /// A game that fails within this many seconds is reported to the UI.
+    pub launch_grace_secs: Option<u64>,
+    /// Most detailed messages written to `launcher.log`.
+    pub log_level: LogLevel,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiConfig {
+    /// Drova services root, e.g. for a staging environment.
+    pub base_url: String,
+    /// Per request, including reading the body.
+    pub timeout_secs: u64,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_API_BASE_URL.to_string(),
+            timeout_secs: DEFAULT_HTTP_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl ApiConfig {
+    /// `path` starts with `/`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+/// Local copies of card and page pictures.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImageCacheConfig {
+    pub enabled: bool,
+    pub ttl_secs: u64,
+    /// Oldest files are removed once the cache grows past this size.
+    pub max_size_mb: Option<u64>,
+}
+
+impl Default for ImageCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: DEFAULT_IMAGE_CACHE_TTL_SECS,
+            max_size_mb: None,
+        }
+    }
+}
+
+impl ImageCacheConfig {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+
+    pub fn max_size_bytes(&self) -> Option<u64> {
+        self.max_size_mb.map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ParentalConfig {
+    /// Highest allowed `ageGroup`, e.g. `12` for `12+`; unset turns the
+    /// mode off.
+    pub max_age: Option<u8>,
+    /// Salted hash of the PIN that lifts the restriction until the next
+    /// lock or restart, as `--hash-pin` prints it.
+    pub pin_hash: Option<String>,
+    /// Also hide games without an `ageGroup`.
+    pub hide_unrated: bool,
+}
+
+impl ParentalConfig {
+    pub fn age_limit(&self) -> Option<AgeLimit> {
+        self.max_age.map(|max_age| AgeLimit {
+            max_age,
+            hide_unrated: self.hide_unrated,
+        })
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct KioskConfig {
+    /// Closing the window or Alt+F4 asks for the owner PIN.
+    pub enabled: bool,
+    /// Hash of the owner PIN, as for `parental.pinHash`.
+    pub pin_hash: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CredentialsConfig {
+    /// Providers tried in order until one returns a station.
+    pub providers: Vec<CredentialSource>,
+    /// Stations for the `config` provider; the first one is the default.
+    pub stations: Vec<StationCredentials>,
+}
+
+impl Default for CredentialsConfig {
+    fn default() -> Self {
+        Self {
+            providers: default_credential_sources(),
+            stations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StationCredentials {
+    pub uuid: String,
+    pub auth_token: String,
+}
+
+/// Lists that are present in the config replace the built-in ones; absent
+/// lists keep them. Patterns are case-insensitive and support `*`.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DesktopRules {
+    pub product_ids: Vec<String>,
+    pub titles: Vec<String>,
+    pub display_names: Vec<String>,
+    /// Also treat products flagged `useDefaultDesktop` as Desktop.
+    pub use_default_desktop: bool,
+}
+
+impl Default for DesktopRules {
+    fn default() -> Self {
+        Self {
+            product_ids: vec![DESKTOP_PRODUCT_ID.to_string()],
+            titles: vec!["desktop".to_string()],
+            display_names: vec!["рабочий стол".to_string()],
+            use_default_desktop: false,
+        }
+    }
+}
+
+impl DesktopRules {
+    pub fn matches_product_id(&self, product_id: &str) -> bool {
+        self.product_ids
+            .iter()
+            .any(|id| id.eq_ignore_ascii_case(product_id))
+    }
+
+    pub fn matches_title(&self, title: &str) -> bool {
+        matches_any(&self.titles, title)
+    }
+
+    pub fn matches_display_name(&self, display_name: &str) -> bool {
+        matches_any(&self.display_names, display_name)
+    }
+}
+
+fn matches_any(patterns: &[String], value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    !value.is_empty()
+        && patterns
+            .iter()
+            .any(|pattern| wildcard_match(&pattern.trim().to_lowercase(), &value))
+}
+
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LaunchProfile {
+    pub name: String,
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HookSet {
+    pub pre_launch: Vec<HookCommand>,
+    pub post_exit: Vec<HookCommand>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HookCommand {
+    /// Run through the system shell (`cmd /C` on Windows, `sh -c` elsewhere).
+    pub command: String,
+    #[serde(default)]
+    pub work_dir: Option<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Only meaningful for pre-launch hooks: a failure cancels the launch.
+    #[serde(default = "default_true")]
+    pub abort_on_failure: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl LocalConfig {
+    pub fn product_profiles(&self, product_id: &str) -> &[LaunchProfile] {
+        self.profiles
+            .get(product_id)
+            .map(|profiles| profiles.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn pre_launch_hooks(&self, product_id: &str) -> Vec<HookCommand> {
+        self.collect_hooks(product_id, |set| &set.pre_launch)
+    }
+
+    pub fn post_exit_hooks(&self, product_id: &str) -> Vec<HookCommand> {
+        self.collect_hooks(product_id, |set| &set.post_exit)
+    }
+
+    fn collect_hooks(
+        &self,
+        product_id: &str,
+        stage: impl Fn(&HookSet) -> &Vec<HookCommand>,
+    ) -> Vec<HookCommand> {
+        let mut hooks = stage(&self.hooks).clone();
+        if let Some(product) = self.product_hooks.get(product_id) {
+            hooks.extend(stage(product).iter().cloned());
+        }
+        hooks
+    }
+
+    pub fn auto_refresh_interval(&self) -> Option<Duration> {
+        match self.auto_refresh_secs.unwrap_or(DEFAULT_AUTO_REFRESH_SECS) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    pub fn find_profile(&self, product_id: &str, name: &str) -> Option<&LaunchProfile> {
+        self.product_profiles(product_id)
+            .iter()
+            .find(|profile| profile.name == name)
+    }
+
+    pub fn launch_grace_period(&self) -> Duration {
+        Duration::from_secs(self.launch_grace_secs.unwrap_or(DEFAULT_LAUNCH_GRACE_SECS))
+    }
+
+    /// Environment variables win over the file so a single value can be
+    /// changed without editing it. Empty variables are ignored. Returns
+    /// one message per variable that could not be parsed.
+    fn apply_env_overrides(&mut self, var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let var = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+        let mut problems = Vec::new();
+        let mut number = |name: &str| {
+            let value = var(name)?;
+            match value.trim().parse::<u64>() {
+                Ok(number) => Some(number),
+                Err(_) => {
+                    problems.push(tr_fmt(
+                        "{}: ожидается целое число, получено «{}»",
+                        &[&name, &value],
+                    ));
+                    None
+                }
+            }
+        };
+        if let Some(ttl) = number("DROVA_IMAGE_CACHE_TTL_SECS") {
+            self.image_cache.ttl_secs = ttl;
+        }
+        if let Some(max_size) = number("DROVA_IMAGE_CACHE_MAX_MB") {
+            self.image_cache.max_size_mb = Some(max_size);
+        }
+        if let Some(timeout) = number("DROVA_HTTP_TIMEOUT_SECS") {
+            self.api.timeout_secs = timeout;
+        }
+        if let Some(grace) = number("DROVA_LAUNCH_GRACE_SECS") {
+            self.launch_grace_secs = Some(grace);
+        }
+
+        if let Some(value) = var("DROVA_IMAGE_CACHE") {
+            match value.trim().to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => self.image_cache.enabled = true,
+                "0" | "false" | "no" | "off" => self.image_cache.enabled = false,
+                _ => problems.push(tr_fmt(
+                    "{}: ожидается 1/0, true/false, yes/no или on/off, получено «{}»",
+                    &[&"DROVA_IMAGE_CACHE", &value],
+                )),
+            }
+        }
+        if let Some(value) = var("DROVA_API_BASE_URL") {
+            self.api.base_url = value.trim().to_string();
+        }
+        if let Some(value) = var("DROVA_LOG_LEVEL") {
+            match serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase())) {
+                Ok(level) => self.log_level = level,
+                Err(_) => problems.push(tr_fmt(
+                    "{}: ожидается off, error, warn, info, debug или trace, получено «{}»",
+                    &[&"DROVA_LOG_LEVEL", &value],
+                )),
+            }
+        }
+        if let Some(value) = var("DROVA_LOCALE") {
+            match serde_json::from_value(serde_json::Value::String(value.trim().to_lowercase())) {
+                Ok(locale) => self.locale = Some(locale),
+                Err(_) => problems.push(tr_fmt(
+                    "{}: ожидается ru или en, получено «{}»",
+                    &[&"DROVA_LOCALE", &value],
+                )),
+            }
+        }
+        problems
+    }
+
+    /// Values that parse but can't work, with the field they came from.
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let is_http = url::Url::parse(&self.api.base_url)
+            .map(|url| matches!(url.scheme(), "http" | "https"))
+            .unwrap_or(false);
+        if !is_http {
+            problems.push(tr_fmt(
+                "api.baseUrl: нужен адрес http(s), получено «{}»",
+                &[&self.api.base_url],
+            ));
+        }
+        if self.api.timeout_secs == 0 {
+            problems.push(tr("api.timeoutSecs: должно быть больше 0").to_string());
+        }
+        if self.image_cache.ttl_secs == 0 {
+            problems.push(tr("imageCache.ttlSecs: должно быть больше 0").to_string());
+        }
+        if self.image_cache.max_size_mb == Some(0) {
+            problems.push(tr("imageCache.maxSizeMb: должно быть больше 0").to_string());
+        }
+        if !self.parental.pin_hash.as_deref().is_none_or(is_pin_hash) {
+            problems.push(tr("parental.pinHash: нужна строка из --hash-pin").to_string());
+        }
+        match self.kiosk.pin_hash.as_deref() {
+            Some(hash) if !is_pin_hash(hash) => {
+                problems.push(tr("kiosk.pinHash: нужна строка из --hash-pin").to_string())
+            }
+            // Without a PIN the launcher could not be closed at all.
+            None if self.kiosk.enabled => {
+                problems.push(tr("kiosk.pinHash: обязателен при kiosk.enabled").to_string())
+            }
+            _ => {}
+        }
+        for (idx, station) in self.credentials.stations.iter().enumerate() {
+            if station.uuid.trim().is_empty() || station.auth_token.trim().is_empty() {
+                problems.push(tr_fmt(
+                    "credentials.stations[{}]: нужны uuid и authToken",
+                    &[&idx],
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// `DROVA_CONFIG` points at an explicit file, otherwise the file lives in
+/// the app config directory.
+pub fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("DROVA_CONFIG") {
+        if !path.trim().is_empty() {
+            return Some(PathBuf::from(path));
+        }
+    }
+    dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER).join(CONFIG_FILE_NAME))
+}
+
+/// Same place as Tauri's `app_log_dir`, so the log can be found before a
+/// Tauri app exists.
+pub fn log_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|dir| dir.join("Library").join("Logs").join(APP_IDENTIFIER))
+    } else {
+        dirs::data_local_dir().map(|dir| dir.join(APP_IDENTIFIER).join("logs"))
+    }
+}
+
+/// Runtime state (e.g. the selected station) lives in the app data
+/// directory, next to where Tauri keeps its own data.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+/// Variables the process was started with; `.env` never replaces them.
+static PROCESS_ENV: OnceLock<HashSet<String>> = OnceLock::new();
+/// Variables currently set from `.env`, so a reload can change or drop them.
+static DOTENV_VARS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Reads `.env` (found like `dotenvy::dotenv`) into the environment, at
+/// startup and again whenever it changes. Unlike `dotenvy::dotenv` a
+/// reload replaces values it set before and removes ones no longer in the
+/// file; variables from the real environment still win.
+pub fn load_dotenv() {
+    let entries = match dotenvy::dotenv_iter() {
+        Ok(iter) => iter.filter_map(Result::ok).collect(),
+        Err(err) if err.not_found() => Vec::new(),
+        Err(err) => {
+            log::warn!("Failed to read .env: {}", err);
+            return;
+        }
+    };
+    apply_dotenv(entries);
+}
+
+fn apply_dotenv(entries: Vec<(String, String)>) {
+    let process_env = PROCESS_ENV.get_or_init(|| {
+        std::env::vars_os()
+            .filter_map(|(name, _)| name.into_string().ok())
+            .collect()
+    });
+    let Ok(mut loaded) = DOTENV_VARS.lock() else {
+        return;
+    };
+    let previous = loaded.take().unwrap_or_default();
+    let mut current = HashSet::new();
+    for (name, value) in entries {
+        if process_env.contains(&name) {
+            continue;
+        }
+        std::env::set_var(&name, value);
+        current.insert(name);
+    }
+    for name in previous.difference(&current) {
+        std::env::remove_var(name);
+    }
+    *loaded = Some(current);
+}
+
+/// Reads the file, applies `DROVA_*` overrides and validates the result.
+/// A missing file is not an error: everything in it is optional.
+pub fn load_local_config() -> Result<LocalConfig, String> {
+    let path = config_path();
+    let contents = match &path {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(contents) => Some(contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(tr_fmt(
+                    "Не удалось прочитать файл настроек {}: {}",
+                    &[&path.display(), &err],
+                ))
+            }
+        },
+        None => None,
+    };
+    let location = path
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+    let mut config = match contents {
+        Some(contents) => parse_local_config(&contents)
+            .map_err(|err| tr_fmt("Ошибка в файле настроек {}: {}", &[&location, &err]))?,
+        None => LocalConfig::default(),
+    };
+
+    let mut problems = config.apply_env_overrides(|name| std::env::var(name).ok());
+    problems.extend(config.validate());
+    if problems.is_empty() {
+        Ok(config)
+    } else {
+        Err(tr_fmt(
+            "Ошибка в файле настроек {}: {}",
+            &[&location, &problems.join("; ")],
+        ))
+    }
+}
+
+fn parse_local_config(contents: &str) -> Result<LocalConfig, String> {
+    serde_json::from_str(contents).map_err(|err| err.to_string())
+}
+
+/// Kiosk settings as far as they can be read from a `launcher.json` that
+/// failed to load, so that a mistake elsewhere in the file does not turn
+/// kiosk mode off.
+pub fn kiosk_on_disk() -> KioskConfig {
+    let Some(path) = config_path() else {
+        return KioskConfig::default();
+    };
+    match fs::read_to_string(path) {
+        Ok(contents) => lenient_kiosk(&contents),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => KioskConfig::default(),
+        Err(_) => locked_kiosk(),
+    }
+}
+
+/// A file that is not JSON counts as kiosk mode without a PIN, as does a
+/// `kiosk.enabled` of the wrong type.
+fn lenient_kiosk(contents: &str) -> KioskConfig {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(contents) else {
+        return locked_kiosk();
+    };
+    let kiosk = match value.get("kiosk") {
+        None | Some(serde_json::Value::Null) => return KioskConfig::default(),
+        Some(kiosk) if !kiosk.is_object() => return locked_kiosk(),
+        Some(kiosk) => kiosk,
+    };
+    KioskConfig {
+        enabled: !matches!(
+            kiosk.get("enabled"),
+            None | Some(serde_json::Value::Null) | Some(serde_json::Value::Bool(false))
+        ),
+        pin_hash: kiosk
+            .get("pinHash")
+            .and_then(serde_json::Value::as_str)
+            .filter(|hash| is_pin_hash(hash))
+            .map(str::to_owned),
+    }
+}
+
+fn locked_kiosk() -> KioskConfig {
+    KioskConfig {
+        enabled: true,
+        ..KioskConfig::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_local_config_profiles() {
+        let config = parse_local_config(
+            r#"{
+                "profiles": {
+                    "p1": [
+                        { "name": "DX11", "args": ["-dx11"], "env": { "DXVK_HUD": "1" } },
+                        { "name": "Windowed", "args": ["-windowed"] }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.product_profiles("p1").len(), 2);
+        assert!(config.product_profiles("p2").is_empty());
+
+        let dx11 = config.find_profile("p1", "DX11").unwrap();
+        assert_eq!(dx11.args, vec!["-dx11"]);
+        assert_eq!(dx11.env.get("DXVK_HUD").map(String::as_str), Some("1"));
+        assert!(config.find_profile("p1", "DX12").is_none());
+    }
+
+    #[test]
+    fn test_parse_local_config_hooks() {
+        let config = parse_local_config(
+            r#"{
+                "hooks": {
+                    "preLaunch": [{ "command": "cleanup.bat", "timeoutSecs": 5 }],
+                    "postExit": [{ "command": "reset.bat" }]
+                },
+                "productHooks": {
+                    "p1": { "preLaunch": [{ "command": "saves.bat", "abortOnFailure": false }] }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let pre = config.pre_launch_hooks("p1");
+        assert_eq!(pre.len(), 2);
+        assert_eq!(pre[0].command, "cleanup.bat");
+        assert_eq!(pre[0].timeout_secs, Some(5));
+        assert!(pre[0].abort_on_failure);
+        assert_eq!(pre[1].command, "saves.bat");
+        assert!(!pre[1].abort_on_failure);
+
+        assert_eq!(config.pre_launch_hooks("p2").len(), 1);
+        assert_eq!(config.post_exit_hooks("p1").len(), 1);
+    }
+
+    #[test]
+    fn test_desktop_rules_defaults_and_override() {
+        let defaults = parse_local_config("{}").unwrap().desktop;
+        assert!(defaults.matches_product_id(DESKTOP_PRODUCT_ID));
+        assert!(defaults.matches_title("Desktop"));
+        assert!(defaults.matches_display_name("Рабочий стол"));
+        assert!(!defaults.use_default_desktop);
+
+        let custom = parse_local_config(
+            r#"{ "desktop": { "titles": ["desktop*"], "useDefaultDesktop": true } }"#,
+        )
+        .unwrap()
+        .desktop;
+        assert!(custom.matches_title("Desktop (Windows 11)"));
+        assert!(!custom.matches_title("My desktop"));
+        assert!(custom.matches_product_id(DESKTOP_PRODUCT_ID));
+        assert!(custom.use_default_desktop);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("desktop", "desktop"));
+        assert!(!wildcard_match("desktop", "desktops"));
+        assert!(wildcard_match("*стол", "рабочий стол"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("a*b*c", "axxcyyb"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_parse_local_config_credentials() {
+        let defaults = parse_local_config("{}").unwrap().credentials;
+        assert_eq!(defaults.providers, default_credential_sources());
+        assert!(defaults.stations.is_empty());
+
+        let credentials = parse_local_config(
+            r#"{
+                "credentials": {
+                    "providers": ["keyring", "config"],
+                    "stations": [{ "uuid": "uuid-1", "authToken": "token-1" }]
+                }
+            }"#,
+        )
+        .unwrap()
+        .credentials;
+        assert_eq!(
+            credentials.providers,
+            vec![CredentialSource::Keyring, CredentialSource::Config]
+        );
+        assert_eq!(credentials.stations[0].auth_token, "token-1");
+
+        assert!(parse_local_config(r#"{ "credentials": { "providers": ["ldap"] } }"#).is_err());
+    }
+
+    #[test]
+    fn test_auto_refresh_interval() {
+        let interval = |json: &str| parse_local_config(json).unwrap().auto_refresh_interval();
+        assert_eq!(
+            interval("{}"),
+            Some(Duration::from_secs(DEFAULT_AUTO_REFRESH_SECS))
+        );
+        assert_eq!(
+            interval(r#"{ "autoRefreshSecs": 60 }"#),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(interval(r#"{ "autoRefreshSecs": 0 }"#), None);
+    }
+
+    #[test]
+    fn test_parse_local_config_parental() {
+        assert_eq!(
+            parse_local_config("{}").unwrap().parental,
+            ParentalConfig::default()
+        );
+        let parental = parse_local_config(r#"{ "parental": { "maxAge": 12, "pinHash": "abc" } }"#)
+            .unwrap()
+            .parental;
+        assert_eq!(parental.max_age, Some(12));
+        assert_eq!(parental.pin_hash.as_deref(), Some("abc"));
+        assert_eq!(
+            parental.age_limit(),
+            Some(AgeLimit {
+                max_age: 12,
+                hide_unrated: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_local_config_api_and_cache() {
+        let defaults = parse_local_config("{}").unwrap();
+        assert_eq!(defaults.api, ApiConfig::default());
+        assert_eq!(defaults.image_cache, ImageCacheConfig::default());
+        assert_eq!(defaults.launch_grace_period(), Duration::from_secs(15));
+        assert!(defaults.validate().is_empty());
+
+        let config = parse_local_config(
+            r#"{
+                "api": { "baseUrl": "https://staging.drova.io/", "timeoutSecs": 5 },
+                "imageCache": { "enabled": true, "maxSizeMb": 2 },
+                "sortMode": "alphabetical"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.api.url("/product-manager/product/listfull2"),
+            "https://staging.drova.io/product-manager/product/listfull2"
+        );
+        assert_eq!(config.api.timeout(), Duration::from_secs(5));
+        assert!(config.image_cache.enabled);
+        assert_eq!(config.image_cache.ttl_secs, DEFAULT_IMAGE_CACHE_TTL_SECS);
+        assert_eq!(config.image_cache.max_size_bytes(), Some(2 * 1024 * 1024));
+        assert_eq!(config.sort_mode, Some(SortMode::Alphabetical));
+    }
+
+    #[test]
+    fn test_apply_env_overrides() {
+        let env: HashMap<&str, &str> = [
+            ("DROVA_IMAGE_CACHE", "on"),
+            ("DROVA_IMAGE_CACHE_TTL_SECS", "60"),
+            ("DROVA_API_BASE_URL", "http://localhost:8080"),
+            ("DROVA_LAUNCH_GRACE_SECS", " 30 "),
+            ("DROVA_LOCALE", "EN"),
+            ("DROVA_LOG_LEVEL", "Debug"),
+            ("DROVA_HTTP_TIMEOUT_SECS", ""),
+        ]
+        .into_iter()
+        .collect();
+        let mut config = parse_local_config(r#"{ "api": { "timeoutSecs": 7 } }"#).unwrap();
+        let problems = config.apply_env_overrides(|name| env.get(name).map(|v| v.to_string()));
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert!(config.image_cache.enabled);
+        assert_eq!(config.image_cache.ttl_secs, 60);
+        assert_eq!(config.api.base_url, "http://localhost:8080");
+        assert_eq!(config.api.timeout_secs, 7);
+        assert_eq!(config.launch_grace_period(), Duration::from_secs(30));
+        assert_eq!(config.locale, Some(Locale::En));
+        assert_eq!(config.log_level, LogLevel::Debug);
+
+        let mut config = LocalConfig::default();
+        let problems = config.apply_env_overrides(|name| match name {
+            "DROVA_IMAGE_CACHE" => Some("maybe".to_string()),
+            "DROVA_LAUNCH_GRACE_SECS" => Some("soon".to_string()),
+            _ => None,
+        });
+        assert_eq!(problems.len(), 2);
+        assert!(problems
+            .iter()
+            .any(|p| p.starts_with("DROVA_LAUNCH_GRACE_SECS")));
+        assert_eq!(config.launch_grace_secs, None);
+    }
+
+    #[test]
+    fn test_validate_reports_each_field() {
+        let config = parse_local_config(
+            r#"{
+                "api": { "baseUrl": "ftp://drova", "timeoutSecs": 0 },
+                "imageCache": { "maxSizeMb": 0 },
+                "parental": { "maxAge": 12, "pinHash": "1234" },
+                "credentials": { "stations": [{ "uuid": "", "authToken": "t" }] }
+            }"#,
+        )
+        .unwrap();
+        let problems = config.validate();
+        let fields: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "api.baseUrl",
+                "api.timeoutSecs",
+                "imageCache.maxSizeMb",
+                "parental.pinHash",
+                "credentials.stations[0]"
+            ]
+        );
+
+        let kiosk = |json: &str| parse_local_config(json).unwrap().validate();
+        assert_eq!(kiosk(r#"{ "kiosk": { "enabled": true } }"#).len(), 1);
+        let hash = crate::parental::test_pin_hash("1234");
+        let json = format!(
+            r#"{{ "kiosk": {{ "enabled": true, "pinHash": "{}" }} }}"#,
+            hash
+        );
+        assert!(kiosk(&json).is_empty());
+    }
+
+    #[test]
+    fn test_parse_local_config_empty_and_invalid() {
+        assert!(parse_local_config("{}").unwrap().profiles.is_empty());
+        assert!(parse_local_config("{ not json").is_err());
+    }
+
+    #[test]
+    fn test_lenient_kiosk_fails_closed() {
+        let hash = crate::parental::test_pin_hash("1234");
+        let kiosk = lenient_kiosk(&format!(
+            r#"{{ "api": {{ "baseUrl": 5 }}, "kiosk": {{ "enabled": true, "pinHash": "{}" }} }}"#,
+            hash
+        ));
+        assert!(kiosk.enabled);
+        assert_eq!(kiosk.pin_hash, Some(hash));
+
+        let kiosk = lenient_kiosk(r#"{ "kiosk": { "enabled": true, "pinHash": "bad" } }"#);
+        assert!(kiosk.enabled);
+        assert_eq!(kiosk.pin_hash, None);
+
+        assert!(lenient_kiosk(r#"{ "kiosk": { "enabled": "yes" } }"#).enabled);
+        assert!(lenient_kiosk("{ not json").enabled);
+        assert!(!lenient_kiosk(r#"{ "api": { "baseUrl": 5 } }"#).enabled);
+        assert!(!lenient_kiosk(r#"{ "kiosk": { "enabled": false } }"#).enabled);
+    }
+
+    #[test]
+    fn test_dotenv_reload_replaces_and_removes() {
+        let dir = std::env::temp_dir().join(format!("drova-dotenv-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".env");
+        let load = |contents: &str| {
+            fs::write(&path, contents).unwrap();
+            apply_dotenv(
+                dotenvy::from_path_iter(&path)
+                    .unwrap()
+                    .map(Result::unwrap)
+                    .collect(),
+            );
+        };
+
+        load("DROVA_TEST_DOTENV_TOKEN=first\nDROVA_TEST_DOTENV_UUID=u1\n");
+        assert_eq!(std::env::var("DROVA_TEST_DOTENV_TOKEN").unwrap(), "first");
+
+        load("DROVA_TEST_DOTENV_TOKEN=second\n");
+        assert_eq!(std::env::var("DROVA_TEST_DOTENV_TOKEN").unwrap(), "second");
+        assert!(std::env::var("DROVA_TEST_DOTENV_UUID").is_err());
+
+        load("");
+        assert!(std::env::var("DROVA_TEST_DOTENV_TOKEN").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
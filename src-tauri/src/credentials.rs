@@ -0,0 +1,438 @@
+use crate::config::{CredentialsConfig, StationCredentials, APP_IDENTIFIER};
+use crate::i18n::{tr, tr_fmt};
+use crate::station::{self, ConfiguredStation, RegistryReader};
+use crate::{logging, StationInfo};
+use serde::Deserialize;
+use std::fmt;
+
+/// Keyring account holding the station UUID; the token is stored under an
+/// account named after the UUID.
+const KEYRING_STATION_ACCOUNT: &str = "station_uuid";
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialSource {
+    Registry,
+    Env,
+    Config,
+    Keyring,
+}
+
+impl CredentialSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            CredentialSource::Registry => "registry",
+            CredentialSource::Env => "env",
+            CredentialSource::Config => "config",
+            CredentialSource::Keyring => "keyring",
+        }
+    }
+}
+
+/// The Esme client writes to the registry, so it goes first on Windows.
+pub fn default_credential_sources() -> Vec<CredentialSource> {
+    if cfg!(target_os = "windows") {
+        vec![
+            CredentialSource::Registry,
+            CredentialSource::Keyring,
+            CredentialSource::Config,
+            CredentialSource::Env,
+        ]
+    } else {
+        vec![
+            CredentialSource::Env,
+            CredentialSource::Config,
+            CredentialSource::Keyring,
+        ]
+    }
+}
+
+/// One place station credentials can come from.
+pub trait CredentialProvider {
+    fn source(&self) -> CredentialSource;
+
+    /// `preferred` is the station the player picked; providers that only
+    /// know a single station ignore it.
+    fn station(&self, preferred: Option<&str>) -> Result<StationInfo, String>;
+
+    fn stations(&self) -> Result<Vec<ConfiguredStation>, String> {
+        self.station(None).map(|info| {
+            vec![ConfiguredStation {
+                info,
+                is_last_server: true,
+            }]
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CredentialAttempt {
+    pub source: CredentialSource,
+    pub error: String,
+}
+
+/// Every provider failed; `attempts` keeps the reason from each of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CredentialError {
+    pub attempts: Vec<CredentialAttempt>,
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.attempts.is_empty() {
+            return write!(
+                f,
+                "{}",
+                tr("Не настроен ни один источник токена и UUID станции")
+            );
+        }
+        let reasons: Vec<String> = self
+            .attempts
+            .iter()
+            .map(|attempt| format!("{}: {}", attempt.source.label(), attempt.error))
+            .collect();
+        write!(
+            f,
+            "{}",
+            tr_fmt(
+                "Не удалось получить токен и UUID станции ({})",
+                &[&reasons.join("; ")]
+            )
+        )
+    }
+}
+
+pub struct CredentialChain {
+    providers: Vec<Box<dyn CredentialProvider>>,
+}
+
+impl CredentialChain {
+    pub fn new(providers: Vec<Box<dyn CredentialProvider>>) -> Self {
+        Self { providers }
+    }
+
+    pub fn from_config(config: &CredentialsConfig) -> Self {
+        Self::new(
+            config
+                .providers
+                .iter()
+                .map(|source| build_provider(*source, config))
+                .collect(),
+        )
+    }
+
+    /// Whatever is returned is also hidden from the log from then on.
+    pub fn station(&self, preferred: Option<&str>) -> Result<StationInfo, CredentialError> {
+        self.first_success(|provider| provider.station(preferred))
+            .inspect(logging::hide_station)
+    }
+
+    /// Stations from the first provider that knows any.
+    pub fn stations(&self) -> Result<Vec<ConfiguredStation>, CredentialError> {
+        self.first_success(|provider| {
+            provider.stations().and_then(|stations| {
+                if stations.is_empty() {
+                    Err(tr("станции не найдены").to_string())
+                } else {
+                    Ok(stations)
+                }
+            })
+        })
+        .inspect(|stations| {
+            for station in stations {
+                logging::hide_station(&station.info);
+            }
+        })
+    }
+
+    /// Asks every provider, not just up to the first that works, and
+    /// returns how many stations each one knows or why it failed.
+    pub fn probe(&self) -> Vec<(CredentialSource, Result<usize, String>)> {
+        self.providers
+            .iter()
+            .map(|provider| {
+                let result = provider.stations().map(|stations| {
+                    for station in &stations {
+                        logging::hide_station(&station.info);
+                    }
+                    stations.len()
+                });
+                (provider.source(), result)
+            })
+            .collect()
+    }
+
+    fn first_success<T>(
+        &self,
+        attempt: impl Fn(&dyn CredentialProvider) -> Result<T, String>,
+    ) -> Result<T, CredentialError> {
+        let mut attempts = Vec::new();
+        for provider in &self.providers {
+            match attempt(provider.as_ref()) {
+                Ok(value) => {
+                    log::info!("Station credentials from {}", provider.source().label());
+                    return Ok(value);
+                }
+                Err(error) => {
+                    log::debug!(
+                        "Credential provider {} failed: {}",
+                        provider.source().label(),
+                        error
+                    );
+                    attempts.push(CredentialAttempt {
+                        source: provider.source(),
+                        error,
+                    });
+                }
+            }
+        }
+        Err(CredentialError { attempts })
+    }
+}
+
+fn build_provider(
+    source: CredentialSource,
+    config: &CredentialsConfig,
+) -> Box<dyn CredentialProvider> {
+    match source {
+        #[cfg(target_os = "windows")]
+        CredentialSource::Registry => Box::new(RegistryProvider(station::WindowsRegistry)),
+        #[cfg(not(target_os = "windows"))]
+        CredentialSource::Registry => Box::new(UnavailableProvider {
+            source,
+            reason: tr("реестр есть только в Windows"),
+        }),
+        CredentialSource::Env => Box::new(EnvProvider),
+        CredentialSource::Config => Box::new(ConfigProvider {
+            stations: config.stations.clone(),
+        }),
+        CredentialSource::Keyring => Box::new(KeyringProvider),
+    }
+}
+
+/// Stations the Esme client registered under `HKLM`.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub struct RegistryProvider<R: RegistryReader>(pub R);
+
+impl<R: RegistryReader> CredentialProvider for RegistryProvider<R> {
+    fn source(&self) -> CredentialSource {
+        CredentialSource::Registry
+    }
+
+    fn station(&self, preferred: Option<&str>) -> Result<StationInfo, String> {
+        station::registry_station(&self.0, preferred)
+    }
+
+    fn stations(&self) -> Result<Vec<ConfiguredStation>, String> {
+        station::list_registry_stations(&self.0)
+    }
+}
+
+/// `DROVA_STATION_UUID` and `DROVA_AUTH_TOKEN`, optionally from `.env`
+/// (see `config::load_dotenv`; variables set outside `.env` take
+/// precedence).
+pub struct EnvProvider;
+
+impl CredentialProvider for EnvProvider {
+    fn source(&self) -> CredentialSource {
+        CredentialSource::Env
+    }
+
+    fn station(&self, _preferred: Option<&str>) -> Result<StationInfo, String> {
+        let uuid = read_env("DROVA_STATION_UUID")?;
+        let token = read_env("DROVA_AUTH_TOKEN")?;
+        log::debug!("Loaded station info from env: token_len={}", token.len());
+        Ok(StationInfo { uuid, token })
+    }
+}
+
+fn read_env(name: &str) -> Result<String, String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| tr_fmt("{} не задан", &[&name]))
+}
+
+/// Stations listed in `credentials.stations` of `launcher.json`.
+pub struct ConfigProvider {
+    stations: Vec<StationCredentials>,
+}
+
+impl CredentialProvider for ConfigProvider {
+    fn source(&self) -> CredentialSource {
+        CredentialSource::Config
+    }
+
+    fn station(&self, preferred: Option<&str>) -> Result<StationInfo, String> {
+        let station = preferred
+            .and_then(|uuid| self.stations.iter().find(|station| station.uuid == uuid))
+            .or_else(|| self.stations.first())
+            .ok_or_else(|| tr("в launcher.json нет станций").to_string())?;
+        Ok(StationInfo {
+            uuid: station.uuid.clone(),
+            token: station.auth_token.clone(),
+        })
+    }
+
+    fn stations(&self) -> Result<Vec<ConfiguredStation>, String> {
+        Ok(self
+            .stations
+            .iter()
+            .enumerate()
+            .map(|(idx, station)| ConfiguredStation {
+                info: StationInfo {
+                    uuid: station.uuid.clone(),
+                    token: station.auth_token.clone(),
+                },
+                is_last_server: idx == 0,
+            })
+            .collect())
+    }
+}
+
+/// Windows Credential Manager, macOS Keychain or the Linux kernel keyring,
+/// under the app identifier as the service name.
+pub struct KeyringProvider;
+
+impl CredentialProvider for KeyringProvider {
+    fn source(&self) -> CredentialSource {
+        CredentialSource::Keyring
+    }
+
+    fn station(&self, _preferred: Option<&str>) -> Result<StationInfo, String> {
+        let uuid = read_keyring(KEYRING_STATION_ACCOUNT)?;
+        let token = read_keyring(&uuid)?;
+        Ok(StationInfo { uuid, token })
+    }
+}
+
+fn read_keyring(account: &str) -> Result<String, String> {
+    let entry = keyring::Entry::new(APP_IDENTIFIER, account).map_err(|err| err.to_string())?;
+    match entry.get_password() {
+        Ok(value) => Ok(value),
+        Err(keyring::Error::NoEntry) => Err(tr_fmt("нет записи {}", &[&account])),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+struct UnavailableProvider {
+    source: CredentialSource,
+    reason: &'static str,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl CredentialProvider for UnavailableProvider {
+    fn source(&self) -> CredentialSource {
+        self.source
+    }
+
+    fn station(&self, _preferred: Option<&str>) -> Result<StationInfo, String> {
+        Err(self.reason.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeProvider {
+        source: CredentialSource,
+        result: Result<&'static str, &'static str>,
+    }
+
+    impl CredentialProvider for FakeProvider {
+        fn source(&self) -> CredentialSource {
+            self.source
+        }
+
+        fn station(&self, _preferred: Option<&str>) -> Result<StationInfo, String> {
+            self.result
+                .map(|uuid| StationInfo {
+                    uuid: uuid.to_string(),
+                    token: format!("token-{}", uuid),
+                })
+                .map_err(|err| err.to_string())
+        }
+    }
+
+    fn fake(
+        source: CredentialSource,
+        result: Result<&'static str, &'static str>,
+    ) -> Box<dyn CredentialProvider> {
+        Box::new(FakeProvider { source, result })
+    }
+
+    #[test]
+    fn test_chain_uses_first_successful_provider() {
+        let chain = CredentialChain::new(vec![
+            fake(CredentialSource::Env, Err("DROVA_STATION_UUID не задан")),
+            fake(CredentialSource::Config, Ok("uuid-config")),
+            fake(CredentialSource::Keyring, Ok("uuid-keyring")),
+        ]);
+        assert_eq!(chain.station(None).unwrap().uuid, "uuid-config");
+        assert_eq!(chain.stations().unwrap()[0].info.uuid, "uuid-config");
+    }
+
+    #[test]
+    fn test_chain_reports_every_failure() {
+        let chain = CredentialChain::new(vec![
+            fake(CredentialSource::Registry, Err("нет ключа")),
+            fake(CredentialSource::Keyring, Err("нет записи station_uuid")),
+        ]);
+        let err = chain.station(None).unwrap_err();
+        assert_eq!(err.attempts.len(), 2);
+        assert_eq!(
+            err.to_string(),
+            "Не удалось получить токен и UUID станции (registry: нет ключа; keyring: нет записи station_uuid)"
+        );
+        assert!(CredentialChain::new(Vec::new()).station(None).is_err());
+    }
+
+    #[test]
+    fn test_chain_probe_asks_every_provider() {
+        let chain = CredentialChain::new(vec![
+            fake(CredentialSource::Config, Ok("uuid-config")),
+            fake(CredentialSource::Keyring, Err("нет записи station_uuid")),
+        ]);
+        assert_eq!(
+            chain.probe(),
+            vec![
+                (CredentialSource::Config, Ok(1)),
+                (
+                    CredentialSource::Keyring,
+                    Err("нет записи station_uuid".to_string())
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_provider_prefers_selection() {
+        let provider = ConfigProvider {
+            stations: vec![
+                StationCredentials {
+                    uuid: "uuid-1".to_string(),
+                    auth_token: "token-1".to_string(),
+                },
+                StationCredentials {
+                    uuid: "uuid-2".to_string(),
+                    auth_token: "token-2".to_string(),
+                },
+            ],
+        };
+        assert_eq!(provider.station(Some("uuid-2")).unwrap().token, "token-2");
+        assert_eq!(provider.station(Some("gone")).unwrap().uuid, "uuid-1");
+        assert_eq!(provider.stations().unwrap().len(), 2);
+
+        let empty = ConfigProvider {
+            stations: Vec::new(),
+        };
+        assert!(empty.station(None).is_err());
+        let chain = CredentialChain::new(vec![Box::new(empty)]);
+        assert_eq!(
+            chain.stations().unwrap_err().attempts[0].error,
+            "станции не найдены"
+        );
+    }
+}
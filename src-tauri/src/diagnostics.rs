@@ -0,0 +1,344 @@
+use crate::config::{config_path, data_dir};
+use crate::i18n::tr;
+use crate::logging;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+const DIAGNOSTICS_DIR_NAME: &str = "diagnostics";
+/// Enough for a few catalog loads with retries.
+const HTTP_HISTORY_MAX: usize = 50;
+const REDACTED: &str = "[redacted]";
+/// Config keys whose values never leave the station.
+const SECRET_CONFIG_KEYS: &[&str] = &["authToken", "pinHash", "uuid"];
+/// Environment variables that are listed without their values.
+const SECRET_ENV_VARS: &[&str] = &["DROVA_AUTH_TOKEN", "DROVA_STATION_UUID"];
+
+/// One Drova API call, newest last.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRecord {
+    /// Increases with every request, so a caller can tell which records
+    /// are its own.
+    pub id: u64,
+    pub at: String,
+    pub url: String,
+    /// Missing when the request failed before a response arrived.
+    pub status: Option<u16>,
+    /// Until the whole body was read.
+    pub elapsed_ms: u64,
+    /// Body size.
+    pub bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+static HTTP_HISTORY: Mutex<VecDeque<HttpRecord>> = Mutex::new(VecDeque::new());
+static NEXT_HTTP_ID: AtomicU64 = AtomicU64::new(0);
+
+/// `result` is the status and body size, or why there was no response.
+pub fn record_http(url: &str, result: Result<(u16, usize), &str>, elapsed: Duration) {
+    let (status, bytes, error) = match result {
+        Ok((status, bytes)) => (Some(status), Some(bytes as u64), None),
+        Err(error) => (None, None, Some(error.to_string())),
+    };
+    let record = HttpRecord {
+        id: NEXT_HTTP_ID.fetch_add(1, Ordering::Relaxed),
+        at: logging::format_timestamp(SystemTime::now()),
+        url: url.to_string(),
+        status,
+        elapsed_ms: elapsed.as_millis() as u64,
+        bytes,
+        error,
+    };
+    if let Ok(mut history) = HTTP_HISTORY.lock() {
+        push_capped(&mut history, record, HTTP_HISTORY_MAX);
+    }
+}
+
+pub fn http_history() -> Vec<HttpRecord> {
+    http_since(0)
+}
+
+/// The id the next recorded request will get.
+pub fn next_http_id() -> u64 {
+    NEXT_HTTP_ID.load(Ordering::Relaxed)
+}
+
+/// Records from `id` on that are still in the history.
+pub fn http_since(id: u64) -> Vec<HttpRecord> {
+    HTTP_HISTORY
+        .lock()
+        .map(|history| {
+            history
+                .iter()
+                .filter(|record| record.id >= id)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn push_capped<T>(items: &mut VecDeque<T>, item: T, max: usize) {
+    if items.len() == max {
+        items.pop_front();
+    }
+    items.push_back(item);
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub files: usize,
+    pub bytes: u64,
+    pub oldest: Option<String>,
+    pub newest: Option<String>,
+}
+
+/// A missing directory is an empty cache.
+pub fn cache_stats(dir: &Path) -> CacheStats {
+    let mut stats = CacheStats::default();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return stats;
+    };
+    let mut oldest: Option<SystemTime> = None;
+    let mut newest: Option<SystemTime> = None;
+    for metadata in entries
+        .filter_map(|entry| entry.ok()?.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+    {
+        stats.files += 1;
+        stats.bytes += metadata.len();
+        if let Ok(modified) = metadata.modified() {
+            oldest = Some(oldest.map_or(modified, |time| time.min(modified)));
+            newest = Some(newest.map_or(modified, |time| time.max(modified)));
+        }
+    }
+    stats.oldest = oldest.map(logging::format_timestamp);
+    stats.newest = newest.map(logging::format_timestamp);
+    stats
+}
+
+/// `launcher.json` as written by the owner with secrets blanked out, and the
+/// `DROVA_*` variables the process sees.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSnapshot {
+    pub path: Option<String>,
+    /// Only a file that parses is included: secrets are removed by key,
+    /// and a broken file may hold tokens that were never loaded.
+    pub contents: Option<Value>,
+    pub parse_error: Option<String>,
+    pub env: BTreeMap<String, String>,
+    /// Why the current file was rejected, if it was.
+    pub error: Option<String>,
+}
+
+pub fn config_snapshot(error: Option<String>) -> ConfigSnapshot {
+    let path = config_path();
+    let text = path.as_ref().and_then(|path| fs::read_to_string(path).ok());
+    let (contents, parse_error) = match text.as_deref().map(redacted_config) {
+        Some(Ok(value)) => (Some(value), None),
+        Some(Err(err)) => (None, Some(err)),
+        None => (None, None),
+    };
+    let env = std::env::vars()
+        .filter(|(name, _)| name.starts_with("DROVA_"))
+        .map(|(name, value)| {
+            let value = if SECRET_ENV_VARS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                value
+            };
+            (name, value)
+        })
+        .collect();
+    ConfigSnapshot {
+        path: path.map(|path| path.display().to_string()),
+        contents,
+        parse_error,
+        env,
+        error,
+    }
+}
+
+fn redacted_config(text: &str) -> Result<Value, String> {
+    let mut value = serde_json::from_str::<Value>(text).map_err(|err| err.to_string())?;
+    redact_config(&mut value);
+    Ok(value)
+}
+
+fn redact_config(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_CONFIG_KEYS.contains(&key.as_str()) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_config(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_config),
+        _ => {}
+    }
+}
+
+/// Files for one zip, in the order they were added. Everything textual
+/// goes through the log redaction on the way in.
+#[derive(Default)]
+pub struct Bundle {
+    entries: Vec<(String, String)>,
+}
+
+impl Bundle {
+    pub fn add_json(&mut self, name: &str, value: &impl Serialize) {
+        let text = serde_json::to_string_pretty(value)
+            .unwrap_or_else(|err| format!("{{\"error\": \"{}\"}}", err));
+        self.entries
+            .push((name.to_string(), logging::redact(&text)));
+    }
+
+    /// Missing or unreadable files are skipped.
+    pub fn add_file(&mut self, name: &str, path: &Path) {
+        if let Ok(bytes) = fs::read(path) {
+            let text = String::from_utf8_lossy(&bytes);
+            self.entries
+                .push((name.to_string(), logging::redact(&text)));
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default();
+        for (name, text) in &self.entries {
+            zip.start_file(name.as_str(), options)
+                .map_err(|err| err.to_string())?;
+            zip.write_all(text.as_bytes())
+                .map_err(|err| err.to_string())?;
+        }
+        zip.finish().map_err(|err| err.to_string())?;
+        Ok(())
+    }
+}
+
+/// `diagnostics/drova-diagnostics-<UTC time>.zip` in the app data folder.
+pub fn bundle_path() -> Result<PathBuf, String> {
+    let dir = data_dir()
+        .ok_or_else(|| tr("Не удалось определить папку данных приложения").to_string())?
+        .join(DIAGNOSTICS_DIR_NAME);
+    let stamp: String = logging::format_timestamp(SystemTime::now())
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric())
+        .collect();
+    Ok(dir.join(format!("drova-diagnostics-{}.zip", stamp)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_push_capped_drops_oldest() {
+        let mut items = VecDeque::new();
+        for item in 0..5 {
+            push_capped(&mut items, item, 3);
+        }
+        assert_eq!(items, VecDeque::from(vec![2, 3, 4]));
+    }
+
+    #[test]
+    fn test_http_since_returns_later_records() {
+        let first = next_http_id();
+        record_http(
+            "https://example.test/a",
+            Ok((200, 10)),
+            Duration::from_millis(5),
+        );
+        record_http(
+            "https://example.test/b",
+            Err("timeout"),
+            Duration::from_secs(30),
+        );
+
+        let records = http_since(first);
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].status, records[0].bytes), (Some(200), Some(10)));
+        assert_eq!(records[1].error.as_deref(), Some("timeout"));
+        assert!(http_since(records[1].id + 1).is_empty());
+    }
+
+    #[test]
+    fn test_redact_config() {
+        let mut value = serde_json::json!({
+            "parental": { "maxAge": 12, "pinHash": "abc" },
+            "credentials": {
+                "providers": ["config"],
+                "stations": [{ "uuid": "u1", "authToken": "t1" }]
+            },
+            "kiosk": { "enabled": true, "pinHash": null }
+        });
+        redact_config(&mut value);
+        assert_eq!(value["parental"]["pinHash"], REDACTED);
+        assert_eq!(value["parental"]["maxAge"], 12);
+        assert_eq!(value["credentials"]["stations"][0]["uuid"], REDACTED);
+        assert_eq!(value["credentials"]["stations"][0]["authToken"], REDACTED);
+        assert_eq!(value["credentials"]["providers"][0], "config");
+        assert!(value["kiosk"]["pinHash"].is_null());
+    }
+
+    #[test]
+    fn test_redacted_config_leaves_out_broken_files() {
+        let broken =
+            r#"{ "credentials": { "stations": [{ "uuid": "u1", "authToken": "secret-token" }] }"#;
+        let err = redacted_config(broken).unwrap_err();
+        assert!(!err.contains("secret-token"));
+
+        let value = redacted_config(
+            r#"{ "credentials": { "stations": [{ "authToken": "secret-token" }] } }"#,
+        )
+        .unwrap();
+        assert!(!value.to_string().contains("secret-token"));
+    }
+
+    #[test]
+    fn test_cache_stats_and_bundle() {
+        let dir = std::env::temp_dir().join(format!("drova-diagnostics-{}", std::process::id()));
+        let cache = dir.join("images");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("a.jpg"), [0u8; 10]).unwrap();
+        fs::write(cache.join("b.png"), [0u8; 5]).unwrap();
+        let stats = cache_stats(&cache);
+        assert_eq!((stats.files, stats.bytes), (2, 15));
+        assert!(stats.oldest.is_some());
+        assert_eq!(cache_stats(&dir.join("missing")), CacheStats::default());
+
+        let mut bundle = Bundle::default();
+        bundle.add_json("cache.json", &stats);
+        bundle.add_file("missing.log", &dir.join("missing.log"));
+        let path = dir.join("bundle.zip");
+        bundle.write(&path).unwrap();
+
+        let mut zip = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(zip.len(), 1);
+        let mut text = String::new();
+        zip.by_name("cache.json")
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.contains("\"bytes\": 15"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::data_dir;
+use crate::i18n::tr;
+use crate::Card;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const FAVORITES_FILE_NAME: &str = "favorites.json";
+
+/// Games the owner pinned and the player starred, by product ID. Stars are
+/// kept in the app data directory so they survive updates and catalog
+/// reloads; pins come from `launcher.json`, which players can't change.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Favorites {
+    /// In display order. Not read from or written to the file.
+    #[serde(skip)]
+    pub pinned: Vec<String>,
+    pub favorites: Vec<String>,
+}
+
+impl Favorites {
+    pub fn toggle_favorite(&mut self, product_id: &str) {
+        toggle(&mut self.favorites, product_id);
+    }
+
+    pub fn mark(&self, card: &mut Card) {
+        card.pinned = self.pinned.contains(&card.product_id);
+        card.favorite = self.favorites.contains(&card.product_id);
+    }
+
+    /// Flagged copies of `cards` with pinned ones first, then favorites.
+    /// Everything else keeps the station order, so unstarring a game puts
+    /// it back where it was.
+    pub fn apply(&self, cards: &[Card]) -> Vec<Card> {
+        let mut cards = cards.to_vec();
+        for card in cards.iter_mut() {
+            self.mark(card);
+        }
+        cards.sort_by_key(|card| self.rank(&card.product_id));
+        cards
+    }
+
+    fn rank(&self, product_id: &str) -> (u8, usize) {
+        if let Some(idx) = self.pinned.iter().position(|id| id == product_id) {
+            (0, idx)
+        } else if self.favorites.iter().any(|id| id == product_id) {
+            (1, 0)
+        } else {
+            (2, 0)
+        }
+    }
+}
+
+fn toggle(ids: &mut Vec<String>, product_id: &str) {
+    match ids.iter().position(|id| id == product_id) {
+        Some(idx) => {
+            ids.remove(idx);
+        }
+        None => ids.push(product_id.to_string()),
+    }
+}
+
+fn favorites_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(FAVORITES_FILE_NAME))
+}
+
+/// The player's stars with the owner's `pinned`. A missing or unreadable
+/// file means nothing is starred yet.
+pub fn load_favorites(pinned: &[String]) -> Favorites {
+    let favorites: Favorites = favorites_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+    Favorites {
+        pinned: pinned.to_vec(),
+        ..favorites
+    }
+}
+
+pub fn save_favorites(favorites: &Favorites) -> Result<(), String> {
+    let path = favorites_path()
+        .ok_or_else(|| tr("Не удалось определить папку данных приложения").to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let contents = serde_json::to_string_pretty(favorites).map_err(|err| err.to_string())?;
+    fs::write(&path, contents).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(product_id: &str) -> Card {
+        Card {
+            product_id: product_id.to_string(),
+            title: product_id.to_uppercase(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_orders_pinned_then_favorites() {
+        let favorites = Favorites {
+            pinned: vec!["d".to_string(), "b".to_string()],
+            favorites: vec!["c".to_string(), "b".to_string()],
+        };
+        let cards = favorites.apply(&[card("a"), card("b"), card("c"), card("d"), card("e")]);
+        let order: Vec<&str> = cards.iter().map(|c| c.product_id.as_str()).collect();
+        assert_eq!(order, vec!["d", "b", "c", "a", "e"]);
+        assert!(cards[0].pinned && !cards[0].favorite);
+        assert!(cards[1].pinned && cards[1].favorite);
+        assert!(!cards[2].pinned && cards[2].favorite);
+        assert!(!cards[3].pinned && !cards[3].favorite);
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut favorites = Favorites::default();
+        favorites.toggle_favorite("a");
+        assert_eq!(favorites.favorites, vec!["a".to_string()]);
+        favorites.toggle_favorite("a");
+        assert!(favorites.favorites.is_empty());
+    }
+
+    #[test]
+    fn test_pins_stay_out_of_the_file() {
+        let favorites = Favorites {
+            pinned: vec!["a".to_string()],
+            favorites: vec!["b".to_string()],
+        };
+        let json = serde_json::to_string(&favorites).unwrap();
+        assert_eq!(json, r#"{"favorites":["b"]}"#);
+        let parsed: Favorites =
+            serde_json::from_str(r#"{ "pinned": ["x"], "favorites": ["b"] }"#).unwrap();
+        assert!(parsed.pinned.is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::config::HookCommand;
+use crate::i18n::tr_fmt;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 30;
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const PIPE_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Runs owner hooks one after another. A failing hook with
+/// `abort_on_failure` stops the sequence and returns the reason; other
+/// failures are only logged.
+pub fn run_hooks(
+    stage: &str,
+    hooks: &[HookCommand],
+    env: &[(String, String)],
+) -> Result<(), String> {
+    for hook in hooks {
+        match run_hook(stage, hook, env) {
+            Ok(()) => {}
+            Err(err) if hook.abort_on_failure => return Err(err),
+            Err(err) => log::warn!("Ignoring failed {} hook: {}", stage, err),
+        }
+    }
+    Ok(())
+}
+
+fn run_hook(stage: &str, hook: &HookCommand, env: &[(String, String)]) -> Result<(), String> {
+    let mut command = shell_command(&hook.command);
+    if let Some(work_dir) = hook.work_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        command.current_dir(work_dir);
+    }
+    command
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("`{}`: {}", hook.command, err))?;
+    let tree = ProcessTree::attach(&child);
+    let stdout = drain_pipe(child.stdout.take());
+    let stderr = drain_pipe(child.stderr.take());
+
+    let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() >= deadline => {
+                match &tree {
+                    Some(tree) => tree.kill(&mut child),
+                    None => {
+                        let _ = child.kill();
+                    }
+                }
+                let _ = child.wait();
+                break None;
+            }
+            Ok(None) => std::thread::sleep(HOOK_POLL_INTERVAL),
+            Err(err) => return Err(format!("`{}`: {}", hook.command, err)),
+        }
+    };
+
+    log::info!(
+        "Hook [{}] `{}` finished with {:?}\nstdout: {}\nstderr: {}",
+        stage,
+        hook.command,
+        status.and_then(|status| status.code()),
+        collect_pipe(stdout).trim_end(),
+        collect_pipe(stderr).trim_end()
+    );
+
+    match status {
+        None => Err(tr_fmt(
+            "`{}`: превышено время ожидания ({} с)",
+            &[&hook.command, &timeout.as_secs()],
+        )),
+        Some(status) if !status.success() => Err(tr_fmt(
+            "`{}`: код выхода {}",
+            &[
+                &hook.command,
+                &status
+                    .code()
+                    .map(|code| code.to_string())
+                    .unwrap_or_else(|| "—".to_string()),
+            ],
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn shell_command(line: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut command = Command::new("cmd");
+    command
+        .arg("/C")
+        .raw_arg(line)
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell_command(line: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(line).process_group(0);
+    command
+}
+
+/// The hook shell and everything it starts, so that a timeout does not
+/// leave the actual command running. On Windows this is a Job Object the
+/// shell is put into right after it starts; processes it spawns later
+/// join the job too.
+#[cfg(target_os = "windows")]
+struct ProcessTree {
+    job: windows_sys::Win32::Foundation::HANDLE,
+}
+
+#[cfg(target_os = "windows")]
+impl ProcessTree {
+    fn attach(child: &Child) -> Option<Self> {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::Foundation::CloseHandle;
+        use windows_sys::Win32::System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW};
+
+        // SAFETY: both handles are valid here; the job is closed on failure
+        // or when the tree is dropped.
+        unsafe {
+            let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+            if job == 0 {
+                return None;
+            }
+            if AssignProcessToJobObject(job, child.as_raw_handle() as _) == 0 {
+                CloseHandle(job);
+                return None;
+            }
+            Some(Self { job })
+        }
+    }
+
+    fn kill(&self, child: &mut Child) {
+        use windows_sys::Win32::System::JobObjects::TerminateJobObject;
+
+        // SAFETY: `job` stays open until the tree is dropped.
+        if unsafe { TerminateJobObject(self.job, 1) } == 0 {
+            let _ = child.kill();
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from `CreateJobObjectW` and is closed once.
+        unsafe { windows_sys::Win32::Foundation::CloseHandle(self.job) };
+    }
+}
+
+/// The hook shell's process group, which `shell_command` starts it in.
+#[cfg(not(target_os = "windows"))]
+struct ProcessTree {
+    group: libc::pid_t,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl ProcessTree {
+    fn attach(child: &Child) -> Option<Self> {
+        libc::pid_t::try_from(child.id())
+            .ok()
+            .map(|group| Self { group })
+    }
+
+    fn kill(&self, child: &mut Child) {
+        // SAFETY: plain syscall; the shell has not been waited for yet, so
+        // the group id still belongs to it.
+        if unsafe { libc::kill(-self.group, libc::SIGKILL) } != 0 {
+            let _ = child.kill();
+        }
+    }
+}
+
+fn drain_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> Option<mpsc::Receiver<String>> {
+    let mut pipe = pipe?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = pipe.read_to_end(&mut buffer);
+        let _ = sender.send(String::from_utf8_lossy(&buffer).into_owned());
+    });
+    Some(receiver)
+}
+
+/// Processes started by the hook may inherit the pipe and keep it open, so
+/// only wait a little for the output.
+fn collect_pipe(receiver: Option<mpsc::Receiver<String>>) -> String {
+    receiver
+        .and_then(|receiver| receiver.recv_timeout(PIPE_DRAIN_TIMEOUT).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str) -> HookCommand {
+        HookCommand {
+            command: command.to_string(),
+            work_dir: None,
+            timeout_secs: Some(5),
+            abort_on_failure: true,
+        }
+    }
+
+    #[test]
+    fn test_run_hooks_success_and_env() {
+        let env = vec![("DROVA_PRODUCT_ID".to_string(), "p1".to_string())];
+        assert!(run_hooks(
+            "pre-launch",
+            &[hook("test \"$DROVA_PRODUCT_ID\" = p1")],
+            &env
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_run_hooks_failure_aborts() {
+        let err = run_hooks("pre-launch", &[hook("exit 3")], &[]).unwrap_err();
+        assert!(err.contains('3'));
+    }
+
+    #[test]
+    fn test_run_hooks_failure_ignored() {
+        let mut failing = hook("exit 1");
+        failing.abort_on_failure = false;
+        assert!(run_hooks("pre-launch", &[failing], &[]).is_ok());
+    }
+
+    #[test]
+    fn test_run_hooks_timeout() {
+        let mut slow = hook("sleep 5");
+        slow.timeout_secs = Some(0);
+        let started = Instant::now();
+        assert!(run_hooks("pre-launch", &[slow], &[]).is_err());
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_run_hooks_timeout_kills_children() {
+        let dir = std::env::temp_dir().join(format!("drova-hook-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let marker = dir.join("survived");
+        let mut slow = hook(&format!("(sleep 1; touch '{}') & wait", marker.display()));
+        slow.timeout_secs = Some(0);
+        assert!(run_hooks("pre-launch", &[slow], &[]).is_err());
+
+        std::thread::sleep(Duration::from_millis(1500));
+        assert!(!marker.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
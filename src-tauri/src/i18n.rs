@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::RwLock;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    /// Every message is written in Russian first, so it is always complete.
+    #[default]
+    Ru,
+    En,
+}
+
+impl Locale {
+    /// `en-US`, `en_GB.UTF-8` and the like are English; any other language
+    /// gets Russian, the language of the Drova catalog.
+    pub fn from_tag(tag: &str) -> Locale {
+        let language = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        if language == "en" {
+            Locale::En
+        } else {
+            Locale::Ru
+        }
+    }
+}
+
+static LOCALE: RwLock<Option<Locale>> = RwLock::new(None);
+
+/// Switches to the locale from `launcher.json` if set, otherwise the OS
+/// one. Called again whenever the config is reloaded.
+pub fn set_locale(configured: Option<Locale>) {
+    let locale = configured.unwrap_or_else(|| {
+        sys_locale::get_locale()
+            .map(|tag| Locale::from_tag(&tag))
+            .unwrap_or_default()
+    });
+    if let Ok(mut current) = LOCALE.write() {
+        *current = Some(locale);
+    }
+}
+
+/// Russian until `set_locale` runs, which keeps tests and early errors
+/// stable.
+pub fn locale() -> Locale {
+    LOCALE
+        .read()
+        .ok()
+        .and_then(|locale| *locale)
+        .unwrap_or_default()
+}
+
+/// `text` (a Russian message) in the current locale.
+pub fn tr(text: &'static str) -> &'static str {
+    translate(locale(), text)
+}
+
+/// Like `tr`, with each `{}` in the message replaced by the next argument.
+pub fn tr_fmt(text: &'static str, args: &[&dyn Display]) -> String {
+    fill(tr(text), args)
+}
+
+/// A message without an English translation stays Russian rather than
+/// turning into an empty string or a key.
+fn translate(locale: Locale, text: &'static str) -> &'static str {
+    match locale {
+        Locale::Ru => text,
+        Locale::En => EN
+            .iter()
+            .find(|(ru, _)| *ru == text)
+            .map(|(_, en)| *en)
+            .unwrap_or(text),
+    }
+}
+
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(idx) = rest.find("{}") {
+        result.push_str(&rest[..idx]);
+        match args.next() {
+            Some(arg) => result.push_str(&arg.to_string()),
+            None => result.push_str("{}"),
+        }
+        rest = &rest[idx + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+const EN: &[(&str, &str)] = &[
+    ("Получаем токен и UUID станции…", "Getting the station token and UUID…"),
+    ("Загружаем список игр…", "Loading the game list…"),
+    ("Загружаем каталог игр…", "Loading the game catalog…"),
+    ("Загружаем ресурсы…", "Loading resources…"),
+    ("Готово", "Done"),
+    ("Игра", "Game"),
+    ("Игра не найдена", "Game not found"),
+    ("Список игр пуст", "The game list is empty"),
+    ("Станция не найдена", "Station not found"),
+    (
+        "Сервис Drova отклонил токен станции (HTTP {}). Проверьте, что клиент Esme запущен и авторизован",
+        "The Drova service rejected the station token (HTTP {}). Check that the Esme client is running and signed in",
+    ),
+    ("Не найдено описание запуска", "No launch entry found"),
+    ("Пустой путь запуска", "The launch path is empty"),
+    (
+        "Путь запуска вне разрешённых папок",
+        "The launch path is outside the allowed folders",
+    ),
+    ("Профиль запуска «{}» не найден", "Launch profile “{}” not found"),
+    (
+        "Профиль запуска «{}» задаёт аргументы или переменные окружения, а игра открывается по URI",
+        "Launch profile “{}” sets arguments or environment variables, but the game opens through a URI",
+    ),
+    (
+        "Запуск отменён командой перед запуском: {}",
+        "Launch cancelled by a pre-launch command: {}",
+    ),
+    ("`{}`: превышено время ожидания ({} с)", "`{}`: timed out ({} s)"),
+    ("`{}`: код выхода {}", "`{}`: exit code {}"),
+    (
+        "Поддерживаются только http/https ссылки",
+        "Only http/https links are supported",
+    ),
+    ("Не удалось создать file URL", "Could not create a file URL"),
+    (
+        "Игра недоступна в режиме родительского контроля",
+        "This game is not available in parental control mode",
+    ),
+    (
+        "PIN для отключения родительского контроля не задан",
+        "No PIN is set for turning off parental control",
+    ),
+    ("Неверный PIN", "Wrong PIN"),
+    (
+        "Не удалось определить папку данных приложения",
+        "Could not determine the app data folder",
+    ),
+    ("Ошибка в файле настроек {}: {}", "Error in the settings file {}: {}"),
+    (
+        "Не удалось прочитать файл настроек {}: {}",
+        "Could not read the settings file {}: {}",
+    ),
+    (
+        "{}: ожидается целое число, получено «{}»",
+        "{}: expected a whole number, got “{}”",
+    ),
+    (
+        "{}: ожидается 1/0, true/false, yes/no или on/off, получено «{}»",
+        "{}: expected 1/0, true/false, yes/no or on/off, got “{}”",
+    ),
+    (
+        "{}: ожидается off, error, warn, info, debug или trace, получено «{}»",
+        "{}: expected off, error, warn, info, debug or trace, got “{}”",
+    ),
+    (
+        "{}: ожидается ru или en, получено «{}»",
+        "{}: expected ru or en, got “{}”",
+    ),
+    (
+        "api.baseUrl: нужен адрес http(s), получено «{}»",
+        "api.baseUrl: an http(s) address is required, got “{}”",
+    ),
+    (
+        "api.timeoutSecs: должно быть больше 0",
+        "api.timeoutSecs: must be greater than 0",
+    ),
+    (
+        "imageCache.ttlSecs: должно быть больше 0",
+        "imageCache.ttlSecs: must be greater than 0",
+    ),
+    (
+        "imageCache.maxSizeMb: должно быть больше 0",
+        "imageCache.maxSizeMb: must be greater than 0",
+    ),
+    (
+        "parental.pinHash: нужна строка из --hash-pin",
+        "parental.pinHash: use the line printed by --hash-pin",
+    ),
+    (
+        "kiosk.pinHash: нужна строка из --hash-pin",
+        "kiosk.pinHash: use the line printed by --hash-pin",
+    ),
+    (
+        "kiosk.pinHash: обязателен при kiosk.enabled",
+        "kiosk.pinHash: required when kiosk.enabled is set",
+    ),
+    (
+        "Слишком много неверных попыток. Повторите через {} с",
+        "Too many wrong attempts. Try again in {} s",
+    ),
+    (
+        "credentials.stations[{}]: нужны uuid и authToken",
+        "credentials.stations[{}]: uuid and authToken are required",
+    ),
+    (
+        "Не настроен ни один источник токена и UUID станции",
+        "No source for the station token and UUID is configured",
+    ),
+    (
+        "Не удалось получить токен и UUID станции ({})",
+        "Could not get the station token and UUID ({})",
+    ),
+    ("станции не найдены", "no stations found"),
+    ("реестр есть только в Windows", "the registry exists only on Windows"),
+    ("{} не задан", "{} is not set"),
+    ("в launcher.json нет станций", "launcher.json lists no stations"),
+    ("нет записи {}", "no {} entry"),
+    ("Открыть лаунчер", "Open launcher"),
+    ("Недавние игры", "Recent games"),
+    ("Выход", "Exit"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!(Locale::from_tag("en-US"), Locale::En);
+        assert_eq!(Locale::from_tag("en_GB.UTF-8"), Locale::En);
+        assert_eq!(Locale::from_tag("ru-RU"), Locale::Ru);
+        assert_eq!(Locale::from_tag("de-DE"), Locale::Ru);
+        assert_eq!(Locale::from_tag(""), Locale::Ru);
+    }
+
+    #[test]
+    fn test_translate_falls_back_to_russian() {
+        assert_eq!(translate(Locale::En, "Неверный PIN"), "Wrong PIN");
+        assert_eq!(translate(Locale::Ru, "Неверный PIN"), "Неверный PIN");
+        assert_eq!(translate(Locale::En, "Без перевода"), "Без перевода");
+    }
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            fill("`{}`: exit code {}", &[&"run.bat", &1]),
+            "`run.bat`: exit code 1"
+        );
+        assert_eq!(fill("{} is not set", &[]), "{} is not set");
+    }
+
+    #[test]
+    fn test_translations_keep_placeholders() {
+        for (ru, en) in EN {
+            assert_eq!(ru.matches("{}").count(), en.matches("{}").count(), "{}", ru);
+        }
+    }
+}
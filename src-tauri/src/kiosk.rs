@@ -0,0 +1,113 @@
+use crate::config::{self, KioskConfig};
+use crate::parental::PinAttempts;
+use crate::{current_config, tray, SharedState};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager, Window, WindowEvent};
+
+/// In kiosk mode closing the window, which is also what Alt+F4 does, only
+/// asks the UI for the owner PIN. The app quits through `exit_launcher`.
+pub fn guard_close(window: &Window, event: &WindowEvent) {
+    let WindowEvent::CloseRequested { api, .. } = event else {
+        return;
+    };
+    let app = window.app_handle();
+    if !is_enabled(app) {
+        return;
+    }
+    api.prevent_close();
+    log::info!("Kiosk mode: window close intercepted");
+    let _ = app.emit("exit_requested", ());
+}
+
+pub fn is_enabled(app: &AppHandle) -> bool {
+    current(&app.state::<SharedState>()).enabled
+}
+
+/// The kiosk settings in force. While `launcher.json` fails to load, kiosk
+/// mode stays on if either the last valid config or the file on disk has
+/// it on.
+pub fn current(state: &SharedState) -> KioskConfig {
+    let last_valid = current_config(state).kiosk.clone();
+    let failed = state
+        .config_error
+        .lock()
+        .map_or(true, |error| error.is_some());
+    if failed {
+        fail_closed(last_valid, config::kiosk_on_disk())
+    } else {
+        last_valid
+    }
+}
+
+/// A PIN from the file on disk wins, so that a new PIN works right away.
+fn fail_closed(last_valid: KioskConfig, on_disk: KioskConfig) -> KioskConfig {
+    KioskConfig {
+        enabled: last_valid.enabled || on_disk.enabled,
+        pin_hash: on_disk.pin_hash.or(last_valid.pin_hash),
+    }
+}
+
+/// "Выход" in the tray menu: the window comes back with the PIN prompt,
+/// since the tray has no way to enter one.
+pub fn request_exit(app: &AppHandle) {
+    if let Err(err) = tray::restore_launcher(app) {
+        log::warn!("Failed to restore launcher: {}", err);
+    }
+    let _ = app.emit("exit_requested", ());
+}
+
+/// Outside kiosk mode anyone may quit. Wrong PINs count towards the
+/// same lockout as parental unlock.
+pub fn exit_allowed(
+    kiosk: &KioskConfig,
+    attempts: &mut PinAttempts,
+    pin: &str,
+    now: Instant,
+) -> Result<(), String> {
+    if !kiosk.enabled {
+        return Ok(());
+    }
+    attempts.verify(pin, kiosk.pin_hash.as_deref(), now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parental;
+
+    #[test]
+    fn test_exit_allowed() {
+        let now = Instant::now();
+        let mut attempts = PinAttempts::default();
+        let mut kiosk = KioskConfig::default();
+        assert!(exit_allowed(&kiosk, &mut attempts, "", now).is_ok());
+
+        kiosk.enabled = true;
+        assert!(exit_allowed(&kiosk, &mut attempts, "1234", now).is_err());
+        kiosk.pin_hash = Some(parental::test_pin_hash("1234"));
+        assert!(exit_allowed(&kiosk, &mut attempts, "1234", now).is_ok());
+        assert!(exit_allowed(&kiosk, &mut attempts, "0000", now).is_err());
+    }
+
+    #[test]
+    fn test_fail_closed() {
+        let hash = parental::test_pin_hash("1234");
+        let last_valid = KioskConfig {
+            enabled: true,
+            pin_hash: Some(hash.clone()),
+        };
+        let kiosk = fail_closed(last_valid, KioskConfig::default());
+        assert!(kiosk.enabled);
+        assert_eq!(kiosk.pin_hash, Some(hash.clone()));
+
+        let on_disk = KioskConfig {
+            enabled: true,
+            pin_hash: Some(hash.clone()),
+        };
+        let kiosk = fail_closed(KioskConfig::default(), on_disk);
+        assert!(kiosk.enabled);
+        assert_eq!(kiosk.pin_hash, Some(hash));
+
+        assert!(!fail_closed(KioskConfig::default(), KioskConfig::default()).enabled);
+    }
+}
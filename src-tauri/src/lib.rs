@@ -1,30 +1,96 @@
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::Path;
-use std::process::Command;
-use std::sync::Mutex;
-use std::time::{Duration, SystemTime};
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_opener::OpenerExt;
 use url::Url;
 
-const CACHE_TTL_SECS: u64 = 24 * 60 * 60;
+mod config;
+mod credentials;
+mod diagnostics;
+mod favorites;
+mod hooks;
+mod i18n;
+mod kiosk;
+mod logging;
+mod metrics;
+mod parental;
+mod refresh;
+mod search;
+mod sorting;
+mod station;
+mod tray;
+mod watcher;
+
+use config::{
+    load_local_config, ApiConfig, DesktopRules, HookCommand, ImageCacheConfig, LaunchProfile,
+    LocalConfig,
+};
+use credentials::CredentialChain;
+use favorites::{load_favorites, save_favorites, Favorites};
+use hooks::run_hooks;
+use i18n::{tr, tr_fmt, Locale};
+use metrics::Phase;
+use parental::{AgeLimit, PinAttempts};
+use search::{SearchEntry, SearchIndex};
+use sorting::{load_play_stats, load_sort_mode, save_sort_mode, sort_cards, SortMode};
+use station::ConfiguredStation;
+
+const STDERR_TAIL_LINES: usize = 20;
+const RECENT_LAUNCHES_MAX: usize = 5;
 const DESKTOP_PRODUCT_ID: &str = "9fd0eb43-b2bb-4ce3-93b8-9df63f209098";
+/// What the fallback Desktop card launches when no catalog is loaded.
+const DESKTOP_ALIAS: &str = "desktop";
 
-#[cfg(debug_assertions)]
-fn log_debug(message: &str) {
-    eprintln!("[drova-launcher] {}", message);
+#[derive(Default)]
+struct SharedState {
+    /// `launcher.json` with env overrides, as last loaded successfully.
+    config: Mutex<Arc<LocalConfig>>,
+    /// Why the last reload failed; cleared by the next successful one.
+    config_error: Mutex<Option<String>>,
+    catalog: Mutex<Catalog>,
+    recent_launches: Mutex<Vec<String>>,
+    /// Station the current cards were loaded for.
+    current_station: Mutex<Option<StationInfo>>,
+    /// Set once the owner PIN lifts parental mode, until it is locked again.
+    parental_unlocked: Mutex<bool>,
+    /// Wrong owner PINs, for parental unlock and kiosk exit alike.
+    pin_attempts: Mutex<PinAttempts>,
+    /// Held while `play_stats.json` is read and written back, since the
+    /// tray and the UI launch games from different threads.
+    play_stats: Mutex<()>,
+    /// Post-exit hooks of the last game opened through a URI.
+    deferred_post_exit: Mutex<Option<DeferredPostExit>>,
 }
 
-#[cfg(not(debug_assertions))]
-fn log_debug(_message: &str) {}
+/// The launcher never sees the process behind a URI launch, so its
+/// post-exit hooks wait until the launcher window is focused again, which
+/// on a station means the player is back from the game.
+struct DeferredPostExit {
+    product_id: String,
+    hooks: Vec<HookCommand>,
+    env: Vec<(String, String)>,
+    /// Focus changes right after opening the URI come from the game's
+    /// own launcher starting up, not from the player coming back.
+    not_before: Instant,
+}
 
+/// The station's games as last loaded. Kept behind one lock so a
+/// background refresh never leaves launches and cards out of sync.
 #[derive(Default)]
-struct SharedState {
-    launches: Mutex<HashMap<String, LaunchParams>>,
-    desktop_ids: Mutex<HashSet<String>>,
+struct Catalog {
+    cards: Vec<Card>,
+    launches: HashMap<String, LaunchParams>,
+    desktop_ids: HashSet<String>,
+    search: SearchIndex,
+    /// Catalog entries of the station's games, for the game page.
+    products: HashMap<String, ProductMeta>,
 }
 
 #[derive(Clone, Debug)]
@@ -32,9 +98,10 @@ struct LaunchParams {
     exe_path: String,
     work_dir: String,
     args: String,
+    allowed_paths: Vec<String>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 struct Card {
     product_id: String,
@@ -44,15 +111,170 @@ struct Card {
     required_account: String,
     is_free: bool,
     is_desktop: bool,
+    launch_profiles: Vec<String>,
+    pinned: bool,
+    favorite: bool,
+    publisher: String,
+    developer: String,
+    /// As in the catalog, e.g. `16+`.
+    age_group: String,
+    license_type: String,
+    /// Original catalog URL; not cached.
+    page_picture: String,
+}
+
+/// Everything the game page shows. Loaded on demand so cards stay small.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ProductDetails {
+    product_id: String,
+    title: String,
+    description_ru: String,
+    description_en: String,
+    page_picture: String,
+    publisher: String,
+    developer: String,
+    age_group: String,
+    license_type: String,
+    required_account: String,
+    is_free: bool,
+    can_launch: bool,
+    /// Why the game can't be launched, when it can't.
+    launch_error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ParentalStatus {
+    /// The owner set an age limit in `launcher.json`.
+    configured: bool,
+    /// The limit currently applies, i.e. it wasn't lifted with the PIN.
+    active: bool,
+    max_age: Option<u8>,
+    /// A PIN is configured, so the limit can be lifted.
+    can_unlock: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum LaunchHandler {
+    Desktop,
+    Spawn,
+    Opener,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LaunchPreview {
+    product_id: String,
+    handler: LaunchHandler,
+    exe_path: String,
+    args: Vec<String>,
+    work_dir: String,
+    env: BTreeMap<String, String>,
+    uri: Option<String>,
 }
 
 #[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct LaunchFailedPayload {
+    product_id: String,
+    exit_code: Option<i32>,
+    stderr_tail: String,
+    elapsed_ms: u64,
+}
+
+/// Where a catalog load is: one of the `metrics` phases, or finished.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum StatusPhase {
+    Credentials,
+    StationList,
+    Catalog,
+    Resources,
+    Done,
+    Failed,
+}
+
+impl From<Phase> for StatusPhase {
+    fn from(phase: Phase) -> Self {
+        match phase {
+            Phase::Credentials => Self::Credentials,
+            Phase::StationList => Self::StationList,
+            Phase::Catalog => Self::Catalog,
+            Phase::Resources => Self::Resources,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
 struct StatusPayload {
+    /// Localized description of `phase`; for `failed`, the error.
     text: String,
+    phase: StatusPhase,
+    /// Of the whole load, 0–100.
+    percent: u8,
+    error: bool,
+    /// The phase that was running when the load failed.
+    failed_phase: Option<Phase>,
     current: Option<u32>,
     total: Option<u32>,
 }
 
+impl StatusPayload {
+    fn progress(phase: Phase, text: &str, current: Option<u32>, total: Option<u32>) -> Self {
+        Self {
+            text: text.to_string(),
+            phase: phase.into(),
+            percent: progress_percent(phase, current, total),
+            error: false,
+            failed_phase: None,
+            current,
+            total,
+        }
+    }
+
+    fn done() -> Self {
+        Self {
+            text: tr("Готово").to_string(),
+            phase: StatusPhase::Done,
+            percent: 100,
+            error: false,
+            failed_phase: None,
+            current: None,
+            total: None,
+        }
+    }
+
+    fn failed(phase: Option<Phase>, error: &str) -> Self {
+        Self {
+            text: error.to_string(),
+            phase: StatusPhase::Failed,
+            percent: phase.map_or(0, |phase| progress_percent(phase, None, None)),
+            error: true,
+            failed_phase: phase,
+            current: None,
+            total: None,
+        }
+    }
+}
+
+/// Weighted by how long each phase usually takes: images are most of it.
+fn progress_percent(phase: Phase, current: Option<u32>, total: Option<u32>) -> u8 {
+    let (start, end) = match phase {
+        Phase::Credentials => (0, 5),
+        Phase::StationList => (5, 15),
+        Phase::Catalog => (15, 30),
+        Phase::Resources => (30, 100),
+    };
+    let done = match (current, total) {
+        (Some(current), Some(total)) if total > 0 => (end - start) * current.min(total) / total,
+        _ => 0,
+    };
+    (start + done) as u8
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "snake_case")]
 struct StationProduct {
@@ -72,6 +294,12 @@ struct StationProduct {
     title: Option<String>,
     #[serde(default)]
     verified: Option<String>,
+    #[serde(
+        default,
+        alias = "allowedPaths",
+        deserialize_with = "deserialize_path_list"
+    )]
+    allowed_paths: Vec<String>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -85,6 +313,12 @@ struct ProductMeta {
     required_account: Option<String>,
     no_license_requred: Option<bool>,
     use_default_desktop: Option<bool>,
+    publisher: Option<String>,
+    developer: Option<String>,
+    age_group: Option<String>,
+    license_type: Option<String>,
+    description_en: Option<String>,
+    page_picture: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -123,6 +357,16 @@ struct StationDetails {
     hardware: HardwareResponse,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StationSummary {
+    uuid: String,
+    name: String,
+    is_last_server: bool,
+    is_selected: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 struct StationInfo {
     uuid: String,
     token: String,
@@ -130,71 +374,102 @@ struct StationInfo {
 
 #[tauri::command]
 async fn load_cards(app: AppHandle, state: State<'_, SharedState>) -> Result<Vec<Card>, String> {
-    let client = reqwest::Client::new();
-
-    emit_status(&app, "Получаем токен и UUID станции…", None, None);
-    let station = get_station_info()?;
+    let local_config = checked_config(&state)?;
+    let (catalog, station) = fetch_catalog(Some(&app), &local_config).await?;
+    record_seen(&state, &catalog.cards);
+    let cards = arrange_cards(
+        &catalog.cards,
+        &current_favorites(&state),
+        current_age_limit(&state),
+        current_sort_mode(&state),
+    );
+    store_catalog(&state, catalog, station)?;
+    Ok(cards)
+}
 
-    emit_status(&app, "Загружаем список игр…", None, None);
-    let station_products: Vec<StationProduct> =
-        http_get_json(&client, station_products_url(&station.uuid), &station.token).await?;
-    let enabled_products: Vec<StationProduct> = station_products
-        .into_iter()
-        .filter(is_station_product_ready)
-        .collect();
-    if enabled_products.is_empty() {
-        return Err("Список игр пуст".to_string());
+/// Loads the station's games. Progress and the phase timings go to the
+/// `status` and `metrics` events only when `progress` is set, so
+/// background refreshes stay silent; the timings are kept either way.
+async fn fetch_catalog(
+    progress: Option<&AppHandle>,
+    local_config: &LocalConfig,
+) -> Result<(Catalog, StationInfo), String> {
+    let mut timeline = metrics::Timeline::start(progress.is_none());
+    let result = fetch_catalog_timed(progress, local_config, &mut timeline).await;
+    let failed_phase = timeline.current_phase();
+    let metrics = timeline.finish(result.as_ref().err());
+    if let Some(app) = progress {
+        match &result {
+            Ok(_) => emit_status(app, StatusPayload::done()),
+            Err(err) => emit_status(app, StatusPayload::failed(failed_phase, err)),
+        }
+        let _ = app.emit("metrics", metrics);
     }
+    result
+}
+
+async fn fetch_catalog_timed(
+    progress: Option<&AppHandle>,
+    local_config: &LocalConfig,
+    timeline: &mut metrics::Timeline,
+) -> Result<(Catalog, StationInfo), String> {
+    let report = |phase: Phase, text: &str, current: Option<u32>, total: Option<u32>| {
+        if let Some(app) = progress {
+            emit_status(app, StatusPayload::progress(phase, text, current, total));
+        }
+    };
+    let client = http_client(&local_config.api);
+
+    let locale = i18n::locale();
+
+    timeline.phase(Phase::Credentials);
+    report(
+        Phase::Credentials,
+        tr("Получаем токен и UUID станции…"),
+        None,
+        None,
+    );
+    let mut station = get_station_info(local_config)?;
+
+    timeline.phase(Phase::StationList);
+    report(Phase::StationList, tr("Загружаем список игр…"), None, None);
+    let enabled_products = fetch_enabled_products(&client, local_config, &mut station).await?;
 
-    emit_status(&app, "Загружаем каталог игр…", None, None);
+    timeline.phase(Phase::Catalog);
+    report(Phase::Catalog, tr("Загружаем каталог игр…"), None, None);
     let products_full: Vec<ProductMeta> =
-        http_get_json_no_auth(&client, products_full_url()).await?;
+        http_get_json_no_auth(&client, products_full_url(&local_config.api)).await?;
     let product_map = build_product_map(&products_full);
 
-    let desktop_ids = build_desktop_set(&enabled_products, &product_map);
+    let desktop_ids = build_desktop_set(&enabled_products, &product_map, &local_config.desktop);
+    let launch_map = build_launch_map(&enabled_products);
 
-    let mut launch_map: HashMap<String, LaunchParams> = HashMap::new();
-    for item in enabled_products.iter() {
-        launch_map.insert(item.product_id.clone(), build_launch_params(item));
-    }
-
-    emit_status(&app, "Загружаем ресурсы…", None, None);
+    timeline.phase(Phase::Resources);
+    report(Phase::Resources, tr("Загружаем ресурсы…"), None, None);
     let mut cards: Vec<Card> = Vec::new();
+    let mut search_entries: Vec<SearchEntry> = Vec::new();
     for (idx, item) in enabled_products.iter().enumerate() {
         let current = (idx + 1) as u32;
         let total = enabled_products.len() as u32;
-        emit_status(
-            &app,
-            "Загружаем ресурсы…",
+        report(
+            Phase::Resources,
+            tr("Загружаем ресурсы…"),
             Some(current),
             Some(total),
         );
 
         let meta = product_map.get(&item.product_id);
         let image_url = match meta.and_then(|m| m.card_picture.clone()) {
-            Some(url) => {
-                if should_cache_images() {
-                    match cache_image(&client, &url).await {
-                        Ok(Some(cached)) => cached,
-                        _ => url,
-                    }
-                } else {
-                    url
-                }
-            }
+            Some(url) => image_url(&client, &local_config.image_cache, url).await,
             None => String::new(),
         };
 
-        let title = meta
-            .and_then(|m| m.display_name.clone())
-            .or_else(|| meta.and_then(|m| m.title.clone()))
-            .or_else(|| item.title.clone())
-            .unwrap_or_else(|| "Игра".to_string());
+        let title = localized_title(meta, item.title.as_deref(), locale);
 
         let description = meta
             .and_then(|m| m.description_ru.clone())
             .unwrap_or_default();
-        let alt = truncate_chars(&description, 100);
+        let alt = truncate_chars(&localized_description(meta, locale), 100);
 
         let required_account = meta
             .and_then(|m| m.required_account.clone())
@@ -202,8 +477,33 @@ async fn load_cards(app: AppHandle, state: State<'_, SharedState>) -> Result<Vec
         let is_free = meta
             .and_then(|m| m.no_license_requred)
             .unwrap_or(false);
-        let is_desktop = is_desktop_product(item, meta);
-
+        let is_desktop = desktop_ids.contains(&item.product_id);
+        let launch_profiles = local_config
+            .product_profiles(&item.product_id)
+            .iter()
+            .map(|profile| profile.name.clone())
+            .collect();
+
+        search_entries.push(SearchEntry {
+            product_id: item.product_id.clone(),
+            names: [
+                Some(title.clone()),
+                meta.and_then(|m| m.display_name.clone()),
+                meta.and_then(|m| m.title.clone()),
+                item.title.clone(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            description: [
+                Some(description),
+                meta.and_then(|m| m.description_en.clone()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n"),
+        });
         cards.push(Card {
             product_id: item.product_id.clone(),
             title,
@@ -212,34 +512,328 @@ async fn load_cards(app: AppHandle, state: State<'_, SharedState>) -> Result<Vec
             required_account,
             is_free,
             is_desktop,
+            launch_profiles,
+            publisher: meta.and_then(|m| m.publisher.clone()).unwrap_or_default(),
+            developer: meta.and_then(|m| m.developer.clone()).unwrap_or_default(),
+            age_group: meta.and_then(|m| m.age_group.clone()).unwrap_or_default(),
+            license_type: meta
+                .and_then(|m| m.license_type.clone())
+                .unwrap_or_default(),
+            page_picture: meta
+                .and_then(|m| m.page_picture.clone())
+                .unwrap_or_default(),
+            ..Default::default()
         });
     }
 
-    {
-        let mut state_launches = state.launches.lock().map_err(|_| "State locked")?;
-        *state_launches = launch_map;
-        let mut state_desktop = state.desktop_ids.lock().map_err(|_| "State locked")?;
-        *state_desktop = desktop_ids;
+    let products = cards
+        .iter()
+        .filter_map(|card| {
+            let meta = product_map.get(&card.product_id)?;
+            Some((card.product_id.clone(), meta.clone()))
+        })
+        .collect();
+
+    Ok((
+        Catalog {
+            cards,
+            launches: launch_map,
+            desktop_ids,
+            search: SearchIndex::new(search_entries),
+            products,
+        },
+        station,
+    ))
+}
+
+/// English prefers the catalog `title`, Russian `displayName`; each falls
+/// back to the other, then to the station list title.
+fn localized_title(meta: Option<&ProductMeta>, item_title: Option<&str>, locale: Locale) -> String {
+    let display_name = meta.and_then(|m| non_empty(m.display_name.as_deref()));
+    let catalog_title = meta.and_then(|m| non_empty(m.title.as_deref()));
+    let (preferred, fallback) = match locale {
+        Locale::Ru => (display_name, catalog_title),
+        Locale::En => (catalog_title, display_name),
+    };
+    preferred
+        .or(fallback)
+        .or_else(|| non_empty(item_title))
+        .unwrap_or_else(|| tr("Игра"))
+        .to_string()
+}
+
+/// The description in `locale`, or in the other language when it has none.
+fn localized_description(meta: Option<&ProductMeta>, locale: Locale) -> String {
+    let ru = meta.and_then(|m| non_empty(m.description_ru.as_deref()));
+    let en = meta.and_then(|m| non_empty(m.description_en.as_deref()));
+    let (preferred, fallback) = match locale {
+        Locale::Ru => (ru, en),
+        Locale::En => (en, ru),
+    };
+    preferred.or(fallback).unwrap_or_default().to_string()
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+fn store_catalog(
+    state: &SharedState,
+    catalog: Catalog,
+    station: StationInfo,
+) -> Result<(), String> {
+    *state.catalog.lock().map_err(|_| "State locked")? = catalog;
+    *state.current_station.lock().map_err(|_| "State locked")? = Some(station);
+    Ok(())
+}
+
+/// Cards matching `query` from the last loaded catalog, best match first.
+#[tauri::command]
+fn search_cards(state: State<'_, SharedState>, query: String) -> Result<Vec<Card>, String> {
+    let favorites = current_favorites(&state);
+    let age_limit = current_age_limit(&state);
+    let catalog = state.catalog.lock().map_err(|_| "State locked")?;
+    Ok(catalog
+        .search
+        .search(&query)
+        .iter()
+        .filter_map(|product_id| {
+            let mut card = catalog
+                .cards
+                .iter()
+                .find(|card| &card.product_id == product_id)
+                .filter(|card| parental::is_allowed(card, age_limit))?
+                .clone();
+            favorites.mark(&mut card);
+            Some(card)
+        })
+        .collect())
+}
+
+/// Full description, page picture and launch availability of one game from
+/// the last loaded catalog.
+#[tauri::command]
+async fn load_product_details(
+    state: State<'_, SharedState>,
+    product_id: String,
+) -> Result<ProductDetails, String> {
+    let age_limit = current_age_limit(&state);
+    let local_config = current_config(&state);
+    let mut details = {
+        let catalog = state.catalog.lock().map_err(|_| "State locked")?;
+        let card = catalog
+            .cards
+            .iter()
+            .find(|card| card.product_id == product_id)
+            .ok_or_else(|| tr("Игра не найдена").to_string())?;
+        let launch =
+            parental::check_launch(&catalog.cards, &catalog.desktop_ids, &product_id, age_limit)
+                .and_then(|_| {
+                    resolve_launch(&product_id, &catalog.launches, &catalog.desktop_ids, None)
+                });
+        build_product_details(card, catalog.products.get(&product_id), launch)
+    };
+    if !details.page_picture.is_empty() {
+        let client = http_client(&local_config.api);
+        details.page_picture =
+            image_url(&client, &local_config.image_cache, details.page_picture).await;
     }
+    Ok(details)
+}
 
-    Ok(cards)
+fn build_product_details(
+    card: &Card,
+    meta: Option<&ProductMeta>,
+    launch: Result<LaunchPreview, String>,
+) -> ProductDetails {
+    ProductDetails {
+        product_id: card.product_id.clone(),
+        title: card.title.clone(),
+        description_ru: meta
+            .and_then(|m| m.description_ru.clone())
+            .unwrap_or_default(),
+        description_en: meta
+            .and_then(|m| m.description_en.clone())
+            .unwrap_or_default(),
+        page_picture: card.page_picture.clone(),
+        publisher: card.publisher.clone(),
+        developer: card.developer.clone(),
+        age_group: card.age_group.clone(),
+        license_type: card.license_type.clone(),
+        required_account: card.required_account.clone(),
+        is_free: card.is_free,
+        can_launch: launch.is_ok(),
+        launch_error: launch.err(),
+    }
+}
+
+/// Stars or unstars a game for the player. Returns the cards in their
+/// new order.
+#[tauri::command]
+fn toggle_favorite(state: State<'_, SharedState>, product_id: String) -> Result<Vec<Card>, String> {
+    update_favorites(&state, |favorites| favorites.toggle_favorite(&product_id))
+}
+
+fn update_favorites(
+    state: &SharedState,
+    change: impl FnOnce(&mut Favorites),
+) -> Result<Vec<Card>, String> {
+    let mut favorites = current_favorites(state);
+    change(&mut favorites);
+    save_favorites(&favorites)?;
+    let age_limit = current_age_limit(state);
+    let sort_mode = current_sort_mode(state);
+    let catalog = state.catalog.lock().map_err(|_| "State locked")?;
+    Ok(arrange_cards(
+        &catalog.cards,
+        &favorites,
+        age_limit,
+        sort_mode,
+    ))
+}
+
+#[tauri::command]
+fn get_sort_mode(state: State<'_, SharedState>) -> SortMode {
+    current_sort_mode(&state)
+}
+
+/// The player's stars and the owner's pins from the config.
+fn current_favorites(state: &SharedState) -> Favorites {
+    load_favorites(&current_config(state).pinned)
+}
+
+/// The player's choice, else `sortMode` from the config.
+fn current_sort_mode(state: &SharedState) -> SortMode {
+    load_sort_mode(current_config(state).sort_mode.unwrap_or_default())
+}
+
+/// Persists the sort mode and returns the cards in the new order.
+#[tauri::command]
+fn set_sort_mode(state: State<'_, SharedState>, mode: SortMode) -> Result<Vec<Card>, String> {
+    save_sort_mode(mode)?;
+    let age_limit = current_age_limit(&state);
+    let catalog = state.catalog.lock().map_err(|_| "State locked")?;
+    Ok(arrange_cards(
+        &catalog.cards,
+        &current_favorites(&state),
+        age_limit,
+        mode,
+    ))
+}
+
+/// Display order: pinned games, favorites, then the selected sort mode.
+/// Games above the age limit are left out.
+fn arrange_cards(
+    cards: &[Card],
+    favorites: &Favorites,
+    age_limit: Option<AgeLimit>,
+    sort_mode: SortMode,
+) -> Vec<Card> {
+    let mut sorted: Vec<Card> = cards
+        .iter()
+        .filter(|card| parental::is_allowed(card, age_limit))
+        .cloned()
+        .collect();
+    sort_cards(&mut sorted, sort_mode, &load_play_stats());
+    favorites.apply(&sorted)
+}
+
+#[tauri::command]
+fn get_parental_status(state: State<'_, SharedState>) -> Result<ParentalStatus, String> {
+    let parental = &checked_config(&state)?.parental;
+    Ok(ParentalStatus {
+        configured: parental.max_age.is_some(),
+        active: current_age_limit(&state).is_some(),
+        max_age: parental.max_age,
+        can_unlock: parental.pin_hash.is_some(),
+    })
+}
+
+/// Lifts parental mode until `lock_parental_mode` or a restart. Returns
+/// the cards including the games that were hidden.
+#[tauri::command]
+fn unlock_parental_mode(state: State<'_, SharedState>, pin: String) -> Result<Vec<Card>, String> {
+    let local_config = checked_config(&state)?;
+    let parental = &local_config.parental;
+    if parental.pin_hash.is_none() {
+        return Err(tr("PIN для отключения родительского контроля не задан").to_string());
+    }
+    // Held while the slow hash runs, so guesses can't run in parallel.
+    let mut attempts = state.pin_attempts.lock().map_err(|_| "State locked")?;
+    if let Err(err) = attempts.verify(&pin, parental.pin_hash.as_deref(), Instant::now()) {
+        log::warn!("Parental mode unlock rejected: {}", err);
+        return Err(err);
+    }
+    drop(attempts);
+    set_parental_unlocked(&state, true)
+}
+
+#[tauri::command]
+fn lock_parental_mode(state: State<'_, SharedState>) -> Result<Vec<Card>, String> {
+    set_parental_unlocked(&state, false)
+}
+
+/// Quits the launcher; in kiosk mode only with the owner PIN.
+#[tauri::command]
+fn exit_launcher(app: AppHandle, state: State<'_, SharedState>, pin: String) -> Result<(), String> {
+    let mut attempts = state.pin_attempts.lock().map_err(|_| "State locked")?;
+    let allowed = kiosk::exit_allowed(&kiosk::current(&state), &mut attempts, &pin, Instant::now());
+    if let Err(err) = allowed {
+        log::warn!("Kiosk exit rejected: {}", err);
+        return Err(err);
+    }
+    app.exit(0);
+    Ok(())
+}
+
+fn set_parental_unlocked(state: &SharedState, unlocked: bool) -> Result<Vec<Card>, String> {
+    *state.parental_unlocked.lock().map_err(|_| "State locked")? = unlocked;
+    let age_limit = current_age_limit(state);
+    let sort_mode = current_sort_mode(state);
+    let catalog = state.catalog.lock().map_err(|_| "State locked")?;
+    Ok(arrange_cards(
+        &catalog.cards,
+        &current_favorites(state),
+        age_limit,
+        sort_mode,
+    ))
+}
+
+/// The configured age limit unless the owner PIN lifted it. A config that
+/// became invalid keeps the limit from the last valid one.
+fn current_age_limit(state: &SharedState) -> Option<AgeLimit> {
+    age_limit(state, &current_config(state))
+}
+
+fn age_limit(state: &SharedState, local_config: &LocalConfig) -> Option<AgeLimit> {
+    let unlocked = state
+        .parental_unlocked
+        .lock()
+        .map(|unlocked| *unlocked)
+        .unwrap_or(false);
+    if unlocked {
+        None
+    } else {
+        local_config.parental.age_limit()
+    }
 }
 
 #[tauri::command]
-async fn load_station_details() -> Result<StationDetails, String> {
-    let client = reqwest::Client::new();
-    let station = get_station_info()?;
+async fn load_station_details(state: State<'_, SharedState>) -> Result<StationDetails, String> {
+    let local_config = current_config(&state);
+    let client = http_client(&local_config.api);
+    let station = get_station_info(&local_config)?;
     let info: ServerManagerInfo =
-        http_get_json_no_auth(&client, station_info_url(&station.uuid)).await?;
+        http_get_json_no_auth(&client, station_info_url(&local_config.api, &station.uuid)).await?;
     let hardware = match http_get_json_no_auth::<HardwareResponse>(
         &client,
-        station_hardware_url(&station.uuid),
+        station_hardware_url(&local_config.api, &station.uuid),
     )
     .await
     {
         Ok(payload) => payload,
         Err(err) => {
-            log_debug(&format!("Failed to load hardware info: {}", err));
+            log::warn!("Failed to load hardware info: {}", err);
             HardwareResponse::default()
         }
     };
@@ -251,147 +845,690 @@ async fn load_station_details() -> Result<StationDetails, String> {
 }
 
 #[tauri::command]
-fn launch_game(app: AppHandle, state: State<'_, SharedState>, product_id: String) -> Result<(), String> {
-    let desktop_ids = state.desktop_ids.lock().map_err(|_| "State locked")?;
-    if desktop_ids.contains(&product_id) || product_id == "desktop" {
-        background_window(&app)?;
-        return Ok(());
+async fn list_stations(state: State<'_, SharedState>) -> Result<Vec<StationSummary>, String> {
+    let local_config = current_config(&state);
+    let client = http_client(&local_config.api);
+    let stations = list_configured_stations(&local_config)?;
+    let selected = get_station_info(&local_config)
+        .ok()
+        .map(|station| station.uuid);
+
+    let mut summaries = Vec::new();
+    for station in stations {
+        let name = match http_get_json_no_auth::<ServerManagerInfo>(
+            &client,
+            station_info_url(&local_config.api, &station.info.uuid),
+        )
+        .await
+        {
+            Ok(info) => info.name.unwrap_or_default(),
+            Err(err) => {
+                log::warn!(
+                    "Failed to load name of station {}: {}",
+                    station.info.uuid,
+                    err
+                );
+                String::new()
+            }
+        };
+        summaries.push(StationSummary {
+            is_selected: selected.as_deref() == Some(station.info.uuid.as_str()),
+            uuid: station.info.uuid,
+            name,
+            is_last_server: station.is_last_server,
+        });
     }
+    Ok(summaries)
+}
 
-    let launches = state.launches.lock().map_err(|_| "State locked")?;
-    let launch = launches
-        .get(&product_id)
-        .ok_or_else(|| "Не найдено описание запуска".to_string())?;
-    if launch.exe_path.is_empty() {
-        return Err("Пустой путь запуска".to_string());
+#[tauri::command]
+fn select_station(state: State<'_, SharedState>, uuid: String) -> Result<(), String> {
+    let stations = list_configured_stations(&current_config(&state))?;
+    if !stations.iter().any(|station| station.info.uuid == uuid) {
+        return Err(tr("Станция не найдена").to_string());
     }
+    station::save_selected_station(&uuid)
+}
+
+/// Phase timings of recent catalog loads, oldest first, including
+/// background refreshes.
+#[tauri::command]
+fn get_metrics_history() -> Vec<metrics::LoadMetrics> {
+    metrics::history()
+}
+
+/// Writes a zip for support and returns its path: summary, config and
+/// credential sources with secrets removed, recent API calls, image cache
+/// stats, the resolved launch of every game and the logs.
+#[tauri::command(async)]
+fn export_diagnostics(state: State<'_, SharedState>) -> Result<String, String> {
+    let local_config = current_config(&state);
+    let config_error = state
+        .config_error
+        .lock()
+        .map_err(|_| "State locked")?
+        .clone();
+    let station_loaded = state
+        .current_station
+        .lock()
+        .map_err(|_| "State locked")?
+        .is_some();
+    let (cards, launches) = {
+        let catalog = state.catalog.lock().map_err(|_| "State locked")?;
+        let launches: Vec<serde_json::Value> = catalog
+            .cards
+            .iter()
+            .map(|card| {
+                let launch = resolve_launch(
+                    &card.product_id,
+                    &catalog.launches,
+                    &catalog.desktop_ids,
+                    None,
+                );
+                match launch {
+                    Ok(preview) => serde_json::json!({
+                        "productId": card.product_id,
+                        "title": card.title,
+                        "launch": preview,
+                    }),
+                    Err(error) => serde_json::json!({
+                        "productId": card.product_id,
+                        "title": card.title,
+                        "error": error,
+                    }),
+                }
+            })
+            .collect();
+        (catalog.cards.len(), launches)
+    };
 
-    let mut command = Command::new(&launch.exe_path);
-    if !launch.work_dir.is_empty() {
-        command.current_dir(&launch.work_dir);
+    let mut bundle = diagnostics::Bundle::default();
+    bundle.add_json(
+        "summary.json",
+        &serde_json::json!({
+            "createdAt": logging::format_timestamp(SystemTime::now()),
+            "version": env!("CARGO_PKG_VERSION"),
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "locale": i18n::locale(),
+            "stationLoaded": station_loaded,
+            "cards": cards,
+            "ageLimit": current_age_limit(&state).map(|limit| limit.max_age),
+        }),
+    );
+    bundle.add_json("config.json", &diagnostics::config_snapshot(config_error));
+    let credentials: Vec<serde_json::Value> =
+        CredentialChain::from_config(&local_config.credentials)
+            .probe()
+            .into_iter()
+            .map(|(source, result)| match result {
+                Ok(stations) => {
+                    serde_json::json!({ "source": source.label(), "stations": stations })
+                }
+                Err(error) => serde_json::json!({ "source": source.label(), "error": error }),
+            })
+            .collect();
+    bundle.add_json("credentials.json", &credentials);
+    bundle.add_json("http.json", &diagnostics::http_history());
+    bundle.add_json(
+        "image-cache.json",
+        &serde_json::json!({
+            "enabled": local_config.image_cache.enabled,
+            "ttlSecs": local_config.image_cache.ttl_secs,
+            "maxSizeMb": local_config.image_cache.max_size_mb,
+            "dir": image_cache_dir().display().to_string(),
+            "stats": diagnostics::cache_stats(&image_cache_dir()),
+        }),
+    );
+    bundle.add_json("launches.json", &launches);
+    bundle.add_json("metrics.json", &metrics::history());
+    for path in logging::log_files() {
+        if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+            bundle.add_file(&format!("logs/{}", name), &path);
+        }
     }
-    let normalized_args = normalize_launch_args(&launch.args);
-    if let Some(arg) = normalized_args.as_ref() {
-        command.arg(arg);
+
+    let path = diagnostics::bundle_path()?;
+    bundle.write(&path)?;
+    log::info!("Diagnostics written to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+#[tauri::command(async)]
+fn launch_game(
+    app: AppHandle,
+    state: State<'_, SharedState>,
+    product_id: String,
+    profile: Option<String>,
+) -> Result<(), String> {
+    launch_product(&app, &state, &product_id, profile.as_deref())
+}
+
+/// Shared by `launch_game` and the tray quick-launch menu.
+fn launch_product(
+    app: &AppHandle,
+    state: &SharedState,
+    product_id: &str,
+    profile: Option<&str>,
+) -> Result<(), String> {
+    let local_config = checked_config(state)?;
+    let preview = resolve_state_launch(state, &local_config, product_id, profile)?;
+    if preview.handler == LaunchHandler::Desktop {
+        tray::hide_to_tray(app)?;
+        return Ok(());
     }
 
+    let pre_launch = local_config.pre_launch_hooks(product_id);
+    let post_exit = local_config.post_exit_hooks(product_id);
     if cfg!(debug_assertions) {
-        log_debug(&format!(
-            "Debug launch only: exe='{}' work_dir='{}' raw_args='{}' normalized_args={:?}",
-            launch.exe_path, launch.work_dir, launch.args, normalized_args
-        ));
-        if let Some(arg) = normalized_args.as_deref() {
-            if is_epic_uri(arg) {
-                log_debug(&format!("Would open epic uri: {}", arg));
-            }
-        }
+        log::debug!(
+            "Debug launch only: {:?}, pre-launch hooks: {:?}, post-exit hooks: {:?}",
+            preview,
+            pre_launch,
+            post_exit
+        );
         return Ok(());
     }
 
-    if let Some(arg) = normalized_args.as_deref() {
-        if is_epic_uri(arg) {
-            return app
-                .opener()
-                .open_url(arg, None::<&str>)
-                .map_err(|err: tauri_plugin_opener::Error| err.to_string());
+    let env = hook_env(&preview);
+    run_hooks("pre-launch", &pre_launch, &env)
+        .map_err(|err| tr_fmt("Запуск отменён командой перед запуском: {}", &[&err]))?;
+
+    match preview.handler {
+        LaunchHandler::Opener => {
+            let uri = preview.uri.clone().unwrap_or_default();
+            app.opener()
+                .open_url(uri, None::<&str>)
+                .map_err(|err: tauri_plugin_opener::Error| err.to_string())?;
+            if !post_exit.is_empty() {
+                log::info!(
+                    "Post-exit hooks for {} will run when the launcher is focused again",
+                    product_id
+                );
+                let deferred = DeferredPostExit {
+                    product_id: product_id.to_string(),
+                    hooks: post_exit,
+                    env,
+                    not_before: Instant::now() + local_config.launch_grace_period(),
+                };
+                // A new launch means the previous game's session is over.
+                let previous = state
+                    .deferred_post_exit
+                    .lock()
+                    .map_err(|_| "State locked")?
+                    .replace(deferred);
+                if let Some(previous) = previous {
+                    spawn_deferred_post_exit(previous);
+                }
+            }
         }
+        _ => {
+            let child = build_command(&preview)
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|err| err.to_string())?;
+            watch_child(
+                app.clone(),
+                preview.product_id.clone(),
+                child,
+                local_config.launch_grace_period(),
+                post_exit,
+                env,
+            );
+        }
+    }
+
+    if let Ok(mut recent) = state.recent_launches.lock() {
+        push_recent_launch(&mut recent, product_id, RECENT_LAUNCHES_MAX);
     }
-    command.spawn().map_err(|err| err.to_string())?;
+    sorting::record_launch(&state.play_stats, product_id);
+    tray::refresh_tray_menu(app);
     Ok(())
 }
 
+fn push_recent_launch(recent: &mut Vec<String>, product_id: &str, max: usize) {
+    recent.retain(|id| id != product_id);
+    recent.insert(0, product_id.to_string());
+    recent.truncate(max);
+}
+
+#[tauri::command]
+fn preview_launch(
+    state: State<'_, SharedState>,
+    product_id: String,
+    profile: Option<String>,
+) -> Result<LaunchPreview, String> {
+    let local_config = checked_config(&state)?;
+    resolve_state_launch(&state, &local_config, &product_id, profile.as_deref())
+}
+
+fn resolve_state_launch(
+    state: &SharedState,
+    local_config: &LocalConfig,
+    product_id: &str,
+    profile: Option<&str>,
+) -> Result<LaunchPreview, String> {
+    let profile = resolve_profile(local_config, product_id, profile)?;
+    let age_limit = age_limit(state, local_config);
+    let catalog = state.catalog.lock().map_err(|_| "State locked")?;
+    parental::check_launch(&catalog.cards, &catalog.desktop_ids, product_id, age_limit)?;
+    resolve_launch(product_id, &catalog.launches, &catalog.desktop_ids, profile)
+}
+
+/// The config is reloaded whenever `launcher.json` changes, so owner edits
+/// to profiles and hooks apply without restarting the launcher.
+fn resolve_profile<'a>(
+    local_config: &'a LocalConfig,
+    product_id: &str,
+    name: Option<&str>,
+) -> Result<Option<&'a LaunchProfile>, String> {
+    let Some(name) = name.filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+    local_config
+        .find_profile(product_id, name)
+        .map(Some)
+        .ok_or_else(|| tr_fmt("Профиль запуска «{}» не найден", &[&name]))
+}
+
+/// Context passed to owner hooks so one script can serve several games.
+fn hook_env(preview: &LaunchPreview) -> Vec<(String, String)> {
+    vec![
+        ("DROVA_PRODUCT_ID".to_string(), preview.product_id.clone()),
+        ("DROVA_GAME_PATH".to_string(), preview.exe_path.clone()),
+        ("DROVA_WORK_DIR".to_string(), preview.work_dir.clone()),
+    ]
+}
+
+/// Waits for the spawned game on a background thread. An unsuccessful exit
+/// within the grace period is reported to the UI as `launch_failed`; stderr
+/// is drained for the whole lifetime of the process so the pipe never fills.
+/// Post-exit hooks run on the same thread once the game is gone.
+fn watch_child(
+    app: AppHandle,
+    product_id: String,
+    mut child: Child,
+    grace: Duration,
+    post_exit: Vec<HookCommand>,
+    env: Vec<(String, String)>,
+) {
+    let tail = Arc::new(Mutex::new(VecDeque::new()));
+    let reader = child.stderr.take().map(|stderr| {
+        let tail = Arc::clone(&tail);
+        std::thread::spawn(move || drain_stderr(stderr, &tail, STDERR_TAIL_LINES))
+    });
+
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        let status = match child.wait() {
+            Ok(status) => status,
+            Err(err) => {
+                log::warn!("Failed to wait for {}: {}", product_id, err);
+                return;
+            }
+        };
+        let elapsed = started.elapsed();
+        log::info!(
+            "Game {} exited with {:?} after {:?}",
+            product_id,
+            status.code(),
+            elapsed
+        );
+        if !status.success() && elapsed <= grace {
+            if let Some(reader) = reader {
+                // Grandchildren may keep the pipe open; don't hang on them.
+                let deadline = Instant::now() + Duration::from_millis(500);
+                while !reader.is_finished() && Instant::now() < deadline {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+            }
+            let stderr_tail = tail
+                .lock()
+                .map(|tail| tail.iter().cloned().collect::<Vec<_>>().join("\n"))
+                .unwrap_or_default();
+            let _ = app.emit(
+                "launch_failed",
+                LaunchFailedPayload {
+                    product_id: product_id.clone(),
+                    exit_code: status.code(),
+                    stderr_tail,
+                    elapsed_ms: elapsed.as_millis() as u64,
+                },
+            );
+        }
+
+        if let Err(err) = run_hooks("post-exit", &post_exit, &env) {
+            log::warn!("Post-exit hook failed for {}: {}", product_id, err);
+        }
+    });
+}
+
+/// Runs the post-exit hooks of a URI launch once the launcher window gets
+/// focus back after the grace period.
+fn on_window_event(window: &tauri::Window, event: &tauri::WindowEvent) {
+    kiosk::guard_close(window, event);
+    if let tauri::WindowEvent::Focused(true) = event {
+        let state = window.app_handle().state::<SharedState>();
+        let due = state
+            .deferred_post_exit
+            .lock()
+            .ok()
+            .and_then(|mut deferred| take_due(&mut deferred, Instant::now()));
+        if let Some(deferred) = due {
+            spawn_deferred_post_exit(deferred);
+        }
+    }
+}
+
+fn take_due(deferred: &mut Option<DeferredPostExit>, now: Instant) -> Option<DeferredPostExit> {
+    if deferred
+        .as_ref()
+        .is_some_and(|pending| now >= pending.not_before)
+    {
+        deferred.take()
+    } else {
+        None
+    }
+}
+
+fn spawn_deferred_post_exit(deferred: DeferredPostExit) {
+    std::thread::spawn(move || {
+        if let Err(err) = run_hooks("post-exit", &deferred.hooks, &deferred.env) {
+            log::warn!("Post-exit hook failed for {}: {}", deferred.product_id, err);
+        }
+    });
+}
+
+/// Keeps the last `max` lines of `stderr` until it closes. Games on
+/// Windows often write in the OEM or ANSI code page, so lines are decoded
+/// lossily instead of stopping at the first one that isn't UTF-8.
+fn drain_stderr(stderr: impl Read, tail: &Mutex<VecDeque<String>>, max: usize) {
+    let mut reader = BufReader::new(stderr);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(['\n', '\r']).to_string();
+                if let Ok(mut tail) = tail.lock() {
+                    push_tail_line(&mut tail, line, max);
+                }
+            }
+            Err(err) => {
+                log::debug!("Stopped reading game stderr: {}", err);
+                break;
+            }
+        }
+    }
+}
+
+fn push_tail_line(tail: &mut VecDeque<String>, line: String, max: usize) {
+    if tail.len() == max {
+        tail.pop_front();
+    }
+    tail.push_back(line);
+}
+
+/// Resolves everything `launch_game` needs without side effects, so the same
+/// result can be shown to owners by `preview_launch` and `--preview-launch`.
+fn resolve_launch(
+    product_id: &str,
+    launches: &HashMap<String, LaunchParams>,
+    desktop_ids: &HashSet<String>,
+    profile: Option<&LaunchProfile>,
+) -> Result<LaunchPreview, String> {
+    if desktop_ids.contains(product_id) || product_id == DESKTOP_ALIAS {
+        return Ok(LaunchPreview {
+            product_id: product_id.to_string(),
+            handler: LaunchHandler::Desktop,
+            exe_path: String::new(),
+            args: Vec::new(),
+            work_dir: String::new(),
+            env: BTreeMap::new(),
+            uri: None,
+        });
+    }
+
+    let launch = launches
+        .get(product_id)
+        .ok_or_else(|| tr("Не найдено описание запуска").to_string())?;
+    if launch.exe_path.is_empty() {
+        return Err(tr("Пустой путь запуска").to_string());
+    }
+
+    let normalized_args = normalize_launch_args(&launch.args);
+    let uri = normalized_args.clone().filter(|arg| is_epic_uri(arg));
+    let handler = if uri.is_some() {
+        LaunchHandler::Opener
+    } else {
+        LaunchHandler::Spawn
+    };
+    // Opener launches hand a URI to the shell, so there is no path to check.
+    if handler == LaunchHandler::Spawn && !is_path_allowed(&launch.exe_path, &launch.allowed_paths)
+    {
+        log::warn!(
+            "Refusing launch of '{}' outside allowed paths {:?}",
+            launch.exe_path,
+            launch.allowed_paths
+        );
+        return Err(tr("Путь запуска вне разрешённых папок").to_string());
+    }
+
+    // A URI cannot carry extra arguments or environment variables.
+    if let Some(profile) = profile.filter(|profile| {
+        handler == LaunchHandler::Opener && (!profile.args.is_empty() || !profile.env.is_empty())
+    }) {
+        return Err(tr_fmt(
+            "Профиль запуска «{}» задаёт аргументы или переменные окружения, а игра открывается по URI",
+            &[&profile.name],
+        ));
+    }
+
+    let mut args: Vec<String> = normalized_args.into_iter().collect();
+    let mut env = BTreeMap::new();
+    if let Some(profile) = profile {
+        args.extend(profile.args.iter().cloned());
+        env.extend(profile.env.clone());
+    }
+
+    Ok(LaunchPreview {
+        product_id: product_id.to_string(),
+        handler,
+        exe_path: launch.exe_path.clone(),
+        args,
+        work_dir: launch.work_dir.clone(),
+        env,
+        uri,
+    })
+}
+
+fn build_command(preview: &LaunchPreview) -> Command {
+    let mut command = Command::new(&preview.exe_path);
+    if !preview.work_dir.is_empty() {
+        command.current_dir(&preview.work_dir);
+    }
+    command.args(&preview.args);
+    command.envs(&preview.env);
+    command
+}
+
 #[tauri::command]
 fn open_external_url(app: AppHandle, url: String) -> Result<(), String> {
     if !is_http_url(&url) {
-        return Err("Поддерживаются только http/https ссылки".to_string());
+        return Err(tr("Поддерживаются только http/https ссылки").to_string());
+    }
+
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|err: tauri_plugin_opener::Error| err.to_string())
+}
+
+fn emit_status(app: &AppHandle, payload: StatusPayload) {
+    let _ = app.emit("status", payload);
+}
+
+async fn fetch_enabled_products(
+    client: &reqwest::Client,
+    local_config: &LocalConfig,
+    station: &mut StationInfo,
+) -> Result<Vec<StationProduct>, String> {
+    let station_products: Vec<StationProduct> =
+        http_get_json(client, local_config, station, |station| {
+            station_products_url(&local_config.api, &station.uuid)
+        })
+        .await?;
+    let enabled_products: Vec<StationProduct> = station_products
+        .into_iter()
+        .filter(is_station_product_ready)
+        .collect();
+    if enabled_products.is_empty() {
+        return Err(tr("Список игр пуст").to_string());
     }
-
-    app.opener()
-        .open_url(url, None::<&str>)
-        .map_err(|err: tauri_plugin_opener::Error| err.to_string())
+    Ok(enabled_products)
 }
 
-fn background_window(app: &AppHandle) -> Result<(), String> {
-    if let Some(window) = app.get_webview_window("main") {
-        window.minimize().map_err(|err| err.to_string())?;
-    }
-    Ok(())
+/// Esme rotates `auth_token` while the launcher is open, so a 401/403 is
+/// retried once with credentials read again from the providers. `station`
+/// is updated to whatever the retry used.
+async fn http_get_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    local_config: &LocalConfig,
+    station: &mut StationInfo,
+    url_for: impl Fn(&StationInfo) -> String,
+) -> Result<T, String> {
+    let body = match send_with_token(client, &url_for(station), &station.token).await? {
+        TokenResponse::Accepted(body) => body,
+        TokenResponse::Rejected(status) => {
+            log::info!("HTTP {}: re-reading station credentials", status);
+            // The `.env` watch is debounced; don't wait for it.
+            config::load_dotenv();
+            let fresh = get_station_info(local_config)?;
+            if fresh == *station {
+                return Err(auth_error(status));
+            }
+            *station = fresh;
+            match send_with_token(client, &url_for(station), &station.token).await? {
+                TokenResponse::Accepted(body) => body,
+                TokenResponse::Rejected(status) => return Err(auth_error(status)),
+            }
+        }
+    };
+
+    serde_json::from_slice::<T>(&body).map_err(|err| err.to_string())
 }
 
-fn emit_status(app: &AppHandle, text: &str, current: Option<u32>, total: Option<u32>) {
-    let _ = app.emit(
-        "status",
-        StatusPayload {
-            text: text.to_string(),
-            current,
-            total,
-        },
-    );
+enum TokenResponse {
+    /// The response body.
+    Accepted(Vec<u8>),
+    Rejected(reqwest::StatusCode),
 }
 
-async fn http_get_json<T: serde::de::DeserializeOwned>(
+async fn send_with_token(
     client: &reqwest::Client,
-    url: String,
+    url: &str,
     token: &str,
-) -> Result<T, String> {
-    log_debug(&format!("HTTP GET {}", url));
-    let response = client
-        .get(&url)
-        .header("X-Auth-Token", token)
-        .send()
-        .await
-        .map_err(|err| err.to_string())?;
+) -> Result<TokenResponse, String> {
+    let (status, body) = get_recorded(url, client.get(url).header("X-Auth-Token", token)).await?;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        log_debug(&format!("HTTP {} from {}: {}", status, url, body));
+    if is_auth_failure(status) {
+        return Ok(TokenResponse::Rejected(status));
+    }
+    if !status.is_success() {
+        log::warn!(
+            "HTTP {} from {}: {}",
+            status,
+            url,
+            String::from_utf8_lossy(&body)
+        );
         return Err(format!("HTTP {}", status));
     }
+    Ok(TokenResponse::Accepted(body))
+}
+
+/// Sends an API request and reads the whole body. Its status, size and
+/// timing are kept for `export_diagnostics` and the load metrics.
+async fn get_recorded(
+    url: &str,
+    request: reqwest::RequestBuilder,
+) -> Result<(reqwest::StatusCode, Vec<u8>), String> {
+    log::debug!("HTTP GET {}", url);
+    let started = Instant::now();
+    let result = async {
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        Ok::<_, reqwest::Error>((status, body.to_vec()))
+    }
+    .await
+    .map_err(|err| err.to_string());
+    let outcome = result
+        .as_ref()
+        .map(|(status, body)| (status.as_u16(), body.len()))
+        .map_err(String::as_str);
+    diagnostics::record_http(url, outcome, started.elapsed());
+    result
+}
+
+fn is_auth_failure(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN
+}
 
-    response.json::<T>().await.map_err(|err| err.to_string())
+fn auth_error(status: reqwest::StatusCode) -> String {
+    tr_fmt(
+        "Сервис Drova отклонил токен станции (HTTP {}). Проверьте, что клиент Esme запущен и авторизован",
+        &[&status.as_u16()],
+    )
 }
 
 async fn http_get_json_no_auth<T: serde::de::DeserializeOwned>(
     client: &reqwest::Client,
     url: String,
 ) -> Result<T, String> {
-    log_debug(&format!("HTTP GET {}", url));
-    let response = client.get(&url).send().await.map_err(|err| err.to_string())?;
+    let (status, body) = get_recorded(&url, client.get(&url)).await?;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        log_debug(&format!("HTTP {} from {}: {}", status, url, body));
+    if !status.is_success() {
+        log::warn!(
+            "HTTP {} from {}: {}",
+            status,
+            url,
+            String::from_utf8_lossy(&body)
+        );
         return Err(format!("HTTP {}", status));
     }
 
-    response.json::<T>().await.map_err(|err| err.to_string())
+    serde_json::from_slice::<T>(&body).map_err(|err| err.to_string())
 }
 
-fn station_products_url(station_uuid: &str) -> String {
-    format!(
-        "https://services.drova.io/product-manager/serverproduct/list/{}",
-        station_uuid
-    )
+/// A client that gives up after `api.timeoutSecs`.
+fn http_client(api: &ApiConfig) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(api.timeout())
+        .build()
+        .unwrap_or_else(|err| {
+            log::warn!("Failed to build HTTP client: {}", err);
+            reqwest::Client::new()
+        })
 }
 
-fn station_info_url(station_uuid: &str) -> String {
-    format!(
-        "https://services.drova.io/server-manager/servers/public/{}",
+fn station_products_url(api: &ApiConfig, station_uuid: &str) -> String {
+    api.url(&format!(
+        "/product-manager/serverproduct/list/{}",
         station_uuid
-    )
+    ))
 }
 
-fn station_hardware_url(station_uuid: &str) -> String {
-    format!(
-        "https://services.drova.io/server-manager/hardware/list/{}",
-        station_uuid
-    )
+fn station_info_url(api: &ApiConfig, station_uuid: &str) -> String {
+    api.url(&format!("/server-manager/servers/public/{}", station_uuid))
+}
+
+fn station_hardware_url(api: &ApiConfig, station_uuid: &str) -> String {
+    api.url(&format!("/server-manager/hardware/list/{}", station_uuid))
 }
 
-fn products_full_url() -> String {
-    "https://services.drova.io/product-manager/product/listfull2?limit=2000".to_string()
+fn products_full_url(api: &ApiConfig) -> String {
+    api.url("/product-manager/product/listfull2?limit=2000")
 }
 
 fn build_product_map(list: &[ProductMeta]) -> HashMap<String, ProductMeta> {
@@ -405,40 +1542,53 @@ fn build_product_map(list: &[ProductMeta]) -> HashMap<String, ProductMeta> {
 fn build_desktop_set(
     enabled_products: &[StationProduct],
     product_map: &HashMap<String, ProductMeta>,
+    rules: &DesktopRules,
 ) -> HashSet<String> {
     let mut set = HashSet::new();
     for item in enabled_products {
         let meta = product_map.get(&item.product_id);
-        if is_desktop_product(item, meta) {
+        if is_desktop_product(item, meta, rules) {
             set.insert(item.product_id.clone());
         }
     }
     set
 }
 
-fn is_desktop_product(item: &StationProduct, meta: Option<&ProductMeta>) -> bool {
-    if item.product_id == DESKTOP_PRODUCT_ID {
+fn is_desktop_product(
+    item: &StationProduct,
+    meta: Option<&ProductMeta>,
+    rules: &DesktopRules,
+) -> bool {
+    if rules.matches_product_id(&item.product_id) {
         return true;
     }
 
     let title = meta
         .and_then(|m| m.title.clone())
         .or_else(|| item.title.clone())
-        .unwrap_or_default()
-        .to_lowercase();
-    if title == "desktop" {
+        .unwrap_or_default();
+    if rules.matches_title(&title) {
         return true;
     }
 
     let display_name = meta
         .and_then(|m| m.display_name.clone())
-        .unwrap_or_default()
-        .to_lowercase();
-    if display_name == "рабочий стол" {
+        .unwrap_or_default();
+    if rules.matches_display_name(&display_name) {
         return true;
     }
 
-    false
+    rules.use_default_desktop
+        && (item.use_default_desktop == Some(true)
+            || meta.and_then(|m| m.use_default_desktop) == Some(true))
+}
+
+fn build_launch_map(enabled_products: &[StationProduct]) -> HashMap<String, LaunchParams> {
+    let mut map = HashMap::new();
+    for item in enabled_products {
+        map.insert(item.product_id.clone(), build_launch_params(item));
+    }
+    map
 }
 
 fn build_launch_params(item: &StationProduct) -> LaunchParams {
@@ -450,7 +1600,72 @@ fn build_launch_params(item: &StationProduct) -> LaunchParams {
         exe_path,
         work_dir,
         args,
+        allowed_paths: item.allowed_paths.clone(),
+    }
+}
+
+/// `allowed_paths` arrives either as a `;`-separated string (empty for
+/// most products) or as a JSON array, depending on the service version.
+fn deserialize_path_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum PathList {
+        Joined(String),
+        List(Vec<String>),
+    }
+
+    let raw = Option::<PathList>::deserialize(deserializer)?;
+    let items = match raw {
+        Some(PathList::Joined(value)) => value.split(';').map(|item| item.to_string()).collect(),
+        Some(PathList::List(values)) => values,
+        None => Vec::new(),
+    };
+    Ok(items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect())
+}
+
+/// An empty root list means the product is not restricted.
+fn is_path_allowed(path: &str, allowed_roots: &[String]) -> bool {
+    if allowed_roots.is_empty() {
+        return true;
+    }
+    let Some(path) = normalize_windows_path(path) else {
+        return false;
+    };
+    allowed_roots
+        .iter()
+        .any(|root| match normalize_windows_path(root) {
+            Some(root) if !root.is_empty() => {
+                path == root || path.starts_with(&format!("{}\\", root))
+            }
+            _ => false,
+        })
+}
+
+/// Lowercases and unifies separators so Windows paths compare the way the
+/// filesystem does. Paths with `..` segments are rejected outright.
+fn normalize_windows_path(path: &str) -> Option<String> {
+    let unified = unwrap_outer_quotes(path.trim()).replace('/', "\\");
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in unified.split('\\') {
+        match segment {
+            "" | "." => continue,
+            ".." => return None,
+            _ => segments.push(segment),
+        }
     }
+    let prefix = if unified.starts_with("\\\\") {
+        "\\\\"
+    } else {
+        ""
+    };
+    Some(format!("{}{}", prefix, segments.join("\\")).to_lowercase())
 }
 
 fn is_station_product_ready(item: &StationProduct) -> bool {
@@ -463,15 +1678,6 @@ fn is_station_product_ready(item: &StationProduct) -> bool {
     }
 }
 
-fn should_cache_images() -> bool {
-    std::env::var("DROVA_IMAGE_CACHE")
-        .map(|value| {
-            let value = value.trim().to_lowercase();
-            value == "1" || value == "true" || value == "yes" || value == "on"
-        })
-        .unwrap_or(false)
-}
-
 fn truncate_chars(value: &str, max: usize) -> String {
     value.chars().take(max).collect()
 }
@@ -508,8 +1714,23 @@ fn is_http_url(value: &str) -> bool {
         .unwrap_or(false)
 }
 
-async fn cache_image(client: &reqwest::Client, url: &str) -> Result<Option<String>, String> {
-    let cache_dir = std::env::temp_dir().join("drova-launcher").join("images");
+/// The cached copy of `url` when the image cache is on, else `url` itself.
+async fn image_url(client: &reqwest::Client, cache: &ImageCacheConfig, url: String) -> String {
+    if !cache.enabled {
+        return url;
+    }
+    match cache_image(client, cache, &url).await {
+        Ok(Some(cached)) => cached,
+        _ => url,
+    }
+}
+
+async fn cache_image(
+    client: &reqwest::Client,
+    cache: &ImageCacheConfig,
+    url: &str,
+) -> Result<Option<String>, String> {
+    let cache_dir = image_cache_dir();
 
     fs::create_dir_all(&cache_dir).map_err(|err| err.to_string())?;
 
@@ -518,7 +1739,7 @@ async fn cache_image(client: &reqwest::Client, url: &str) -> Result<Option<Strin
 
     if let Ok(metadata) = fs::metadata(&file_path) {
         if let Ok(modified) = metadata.modified() {
-            if !is_expired(modified, Duration::from_secs(CACHE_TTL_SECS)) {
+            if !is_expired(modified, cache.ttl()) {
                 return Ok(Some(file_url(&file_path)?));
             }
         }
@@ -535,10 +1756,51 @@ async fn cache_image(client: &reqwest::Client, url: &str) -> Result<Option<Strin
     }
 
     let bytes = response.bytes().await.map_err(|err| err.to_string())?;
+    metrics::record_download(bytes.len());
     fs::write(&file_path, &bytes).map_err(|err| err.to_string())?;
+    if let Some(max_bytes) = cache.max_size_bytes() {
+        prune_cache(&cache_dir, max_bytes, &file_path);
+    }
     Ok(Some(file_url(&file_path)?))
 }
 
+fn image_cache_dir() -> PathBuf {
+    std::env::temp_dir().join("drova-launcher").join("images")
+}
+
+/// Removes the least recently written files until the cache fits in
+/// `max_bytes`. `keep` (the file just written) is never removed.
+fn prune_cache(cache_dir: &Path, max_bytes: u64, keep: &Path) {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry
+                .metadata()
+                .ok()
+                .filter(|metadata| metadata.is_file())?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((modified, metadata.len(), entry.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort();
+    for (_, size, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path == keep {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => total = total.saturating_sub(size),
+            Err(err) => log::warn!("Failed to prune {}: {}", path.display(), err),
+        }
+    }
+}
+
 fn cache_file_name(url: &str) -> String {
     let mut hasher = Sha1::new();
     hasher.update(url.as_bytes());
@@ -553,7 +1815,7 @@ fn cache_file_name(url: &str) -> String {
 fn file_url(path: &Path) -> Result<String, String> {
     Url::from_file_path(path)
         .map(|url| url.to_string())
-        .map_err(|_| "Не удалось создать file URL".to_string())
+        .map_err(|_| tr("Не удалось создать file URL").to_string())
 }
 
 fn is_expired(modified: SystemTime, ttl: Duration) -> bool {
@@ -563,52 +1825,179 @@ fn is_expired(modified: SystemTime, ttl: Duration) -> bool {
         .unwrap_or(true)
 }
 
-fn get_station_info() -> Result<StationInfo, String> {
-    #[cfg(target_os = "windows")]
+fn get_station_info(local_config: &LocalConfig) -> Result<StationInfo, String> {
+    let selected = station::load_selected_station();
+    CredentialChain::from_config(&local_config.credentials)
+        .station(selected.as_deref())
+        .map_err(|err| err.to_string())
+}
+
+/// All stations the player can switch between.
+fn list_configured_stations(local_config: &LocalConfig) -> Result<Vec<ConfiguredStation>, String> {
+    CredentialChain::from_config(&local_config.credentials)
+        .stations()
+        .map_err(|err| err.to_string())
+}
+
+/// Re-reads `launcher.json` and the `DROVA_*` variables. After a failure
+/// the previous config stays in use for listing cards, while `load_cards`
+/// and launches report the error until the file is fixed.
+fn reload_config(state: &SharedState) -> Result<(), String> {
+    let error = match load_local_config() {
+        Ok(local_config) => {
+            logging::set_level(local_config.log_level);
+            i18n::set_locale(local_config.locale);
+            *state.config.lock().map_err(|_| "State locked")? = Arc::new(local_config);
+            None
+        }
+        Err(err) => {
+            log::error!("{}", err);
+            Some(err)
+        }
+    };
+    *state.config_error.lock().map_err(|_| "State locked")? = error.clone();
+    error.map_or(Ok(()), Err)
+}
+
+/// The last valid config, or the defaults when there never was one.
+fn current_config(state: &SharedState) -> Arc<LocalConfig> {
+    state
+        .config
+        .lock()
+        .map(|config| Arc::clone(&config))
+        .unwrap_or_default()
+}
+
+/// Like `current_config`, but fails while the config on disk is invalid.
+fn checked_config(state: &SharedState) -> Result<Arc<LocalConfig>, String> {
+    if let Some(err) = state
+        .config_error
+        .lock()
+        .map_err(|_| "State locked")?
+        .clone()
     {
-        use winreg::enums::HKEY_LOCAL_MACHINE;
-        use winreg::RegKey;
-
-        let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-        let esme = hklm
-            .open_subkey("SOFTWARE\\ITKey\\Esme")
-            .map_err(|err| err.to_string())?;
-        let station_uuid: String = esme.get_value("last_server").map_err(|err| err.to_string())?;
-        let token_key = format!("SOFTWARE\\ITKey\\Esme\\servers\\{}", station_uuid);
-        let server_key = hklm.open_subkey(token_key).map_err(|err| err.to_string())?;
-        let token: String = server_key.get_value("auth_token").map_err(|err| err.to_string())?;
-
-        return Ok(StationInfo {
-            uuid: station_uuid,
-            token,
-        });
+        return Err(err);
     }
+    Ok(current_config(state))
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        dotenvy::dotenv().ok();
-        let uuid = std::env::var("DROVA_STATION_UUID")
-            .map_err(|_| "DROVA_STATION_UUID не задан".to_string())?;
-        let token = std::env::var("DROVA_AUTH_TOKEN")
-            .map_err(|_| "DROVA_AUTH_TOKEN не задан".to_string())?;
-        log_debug(&format!(
-            "Loaded station info from env: uuid={}, token_len={}",
-            uuid,
-            token.len()
-        ));
-        return Ok(StationInfo { uuid, token });
+fn record_seen(state: &SharedState, cards: &[Card]) {
+    sorting::record_seen(
+        &state.play_stats,
+        cards.iter().map(|card| card.product_id.as_str()),
+    );
+}
+
+/// Fetches the station product list and catalog outside of the UI and
+/// resolves the launch for `product_id`, as `preview_launch` would after
+/// `load_cards`.
+fn cli_preview_launch(
+    state: &SharedState,
+    product_id: &str,
+    profile: Option<&str>,
+) -> Result<LaunchPreview, String> {
+    let local_config = checked_config(state)?;
+    let profile = resolve_profile(&local_config, product_id, profile)?;
+    tauri::async_runtime::block_on(async {
+        let (catalog, _) = fetch_catalog(None, &local_config).await?;
+        resolve_launch(product_id, &catalog.launches, &catalog.desktop_ids, profile)
+    })
+}
+
+/// Handles command line invocations that should not open the launcher
+/// window. Returns the process exit code when one was handled.
+fn run_cli(args: &[String], state: &SharedState) -> Option<i32> {
+    if let Some(position) = args.iter().position(|arg| arg == "--hash-pin") {
+        let Some(pin) = args.get(position + 1) else {
+            eprintln!("Usage: drova-launcher --hash-pin <pin>");
+            return Some(2);
+        };
+        return match parental::hash_pin(pin) {
+            Ok(hash) => {
+                println!("{}", hash);
+                Some(0)
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                Some(1)
+            }
+        };
+    }
+
+    let position = args.iter().position(|arg| arg == "--preview-launch")?;
+    let Some(product_id) = args.get(position + 1) else {
+        eprintln!("Usage: drova-launcher --preview-launch <product_id> [--profile <name>]");
+        return Some(2);
+    };
+    let profile = args
+        .iter()
+        .position(|arg| arg == "--profile")
+        .and_then(|idx| args.get(idx + 1))
+        .map(String::as_str);
+
+    match cli_preview_launch(state, product_id, profile) {
+        Ok(preview) => {
+            match serde_json::to_string_pretty(&preview) {
+                Ok(json) => println!("{}", json),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return Some(1);
+                }
+            }
+            Some(0)
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            Some(1)
+        }
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let args: Vec<String> = std::env::args().collect();
+    logging::init();
+    // Read once here and again when it changes (see `watcher`).
+    config::load_dotenv();
+    let state = SharedState::default();
+    // Without a valid config this still picks the OS language.
+    if reload_config(&state).is_err() {
+        i18n::set_locale(current_config(&state).locale);
+    }
+    if let Some(code) = run_cli(&args, &state) {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
-        .manage(SharedState::default())
+        .manage(state)
         .plugin(tauri_plugin_opener::init())
+        .on_window_event(on_window_event)
+        .setup(|app| {
+            if let Err(err) = tray::setup_tray(app.handle()) {
+                log::warn!("Failed to create tray icon: {}", err);
+            }
+            watcher::watch_station_changes(app.handle().clone());
+            refresh::start_auto_refresh(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             load_cards,
+            search_cards,
+            load_product_details,
+            toggle_favorite,
+            get_sort_mode,
+            set_sort_mode,
+            get_parental_status,
+            unlock_parental_mode,
+            lock_parental_mode,
+            exit_launcher,
+            export_diagnostics,
+            get_metrics_history,
             load_station_details,
+            list_stations,
+            select_station,
             launch_game,
+            preview_launch,
             open_external_url
         ])
         .run(tauri::generate_context!())
@@ -630,6 +2019,12 @@ mod tests {
             required_account: None,
             no_license_requred: None,
             use_default_desktop: None,
+            publisher: None,
+            developer: None,
+            age_group: None,
+            license_type: None,
+            description_en: None,
+            page_picture: None,
         }
     }
 
@@ -643,6 +2038,7 @@ mod tests {
             args: None,
             verified: Some("READY".to_string()),
             title: None,
+            allowed_paths: Vec::new(),
         }
     }
 
@@ -657,6 +2053,7 @@ mod tests {
             args: Some("-custom".to_string()),
             verified: Some("READY".to_string()),
             title: None,
+            allowed_paths: Vec::new(),
         };
         let launch = build_launch_params(&item);
         assert_eq!(launch.exe_path, "C:\\Game.exe");
@@ -664,26 +2061,324 @@ mod tests {
         assert_eq!(launch.args, "-custom");
     }
 
+    fn launch_table(args: &str) -> HashMap<String, LaunchParams> {
+        let mut map = HashMap::new();
+        map.insert(
+            "p1".to_string(),
+            LaunchParams {
+                exe_path: "C:\\Game.exe".to_string(),
+                work_dir: "C:\\Work".to_string(),
+                args: args.to_string(),
+                allowed_paths: Vec::new(),
+            },
+        );
+        map
+    }
+
+    #[test]
+    fn test_resolve_launch_spawn() {
+        let preview =
+            resolve_launch("p1", &launch_table("\"-windowed\""), &HashSet::new(), None).unwrap();
+        assert_eq!(preview.handler, LaunchHandler::Spawn);
+        assert_eq!(preview.exe_path, "C:\\Game.exe");
+        assert_eq!(preview.work_dir, "C:\\Work");
+        assert_eq!(preview.args, vec!["-windowed".to_string()]);
+        assert!(preview.uri.is_none());
+    }
+
+    #[test]
+    fn test_resolve_launch_with_profile() {
+        let mut env = BTreeMap::new();
+        env.insert("DXVK_HUD".to_string(), "1".to_string());
+        let profile = LaunchProfile {
+            name: "DX11".to_string(),
+            args: vec!["-dx11".to_string(), "-windowed".to_string()],
+            env,
+        };
+        let preview = resolve_launch(
+            "p1",
+            &launch_table("-language russian"),
+            &HashSet::new(),
+            Some(&profile),
+        )
+        .unwrap();
+        assert_eq!(
+            preview.args,
+            vec!["-language russian", "-dx11", "-windowed"]
+        );
+        assert_eq!(preview.env.get("DXVK_HUD").map(String::as_str), Some("1"));
+    }
+
+    #[test]
+    fn test_resolve_launch_epic_uri() {
+        let uri = "com.epicgames.launcher://apps/game?action=launch";
+        let preview = resolve_launch("p1", &launch_table(uri), &HashSet::new(), None).unwrap();
+        assert_eq!(preview.handler, LaunchHandler::Opener);
+        assert_eq!(preview.uri.as_deref(), Some(uri));
+    }
+
+    #[test]
+    fn test_resolve_launch_epic_uri_rejects_profile_args() {
+        let uri = "com.epicgames.launcher://apps/game?action=launch";
+        let mut profile = LaunchProfile {
+            name: "DX11".to_string(),
+            args: vec!["-dx11".to_string()],
+            env: BTreeMap::new(),
+        };
+        let err =
+            resolve_launch("p1", &launch_table(uri), &HashSet::new(), Some(&profile)).unwrap_err();
+        assert!(err.contains("DX11"));
+
+        profile.args.clear();
+        let preview =
+            resolve_launch("p1", &launch_table(uri), &HashSet::new(), Some(&profile)).unwrap();
+        assert_eq!(preview.handler, LaunchHandler::Opener);
+    }
+
+    #[test]
+    fn test_resolve_launch_desktop_and_missing() {
+        let mut desktop_ids = HashSet::new();
+        desktop_ids.insert("p1".to_string());
+        let preview = resolve_launch("p1", &launch_table(""), &desktop_ids, None).unwrap();
+        assert_eq!(preview.handler, LaunchHandler::Desktop);
+        assert!(preview.exe_path.is_empty());
+
+        let fallback = resolve_launch("desktop", &HashMap::new(), &HashSet::new(), None).unwrap();
+        assert_eq!(fallback.handler, LaunchHandler::Desktop);
+
+        assert!(resolve_launch("p2", &launch_table(""), &HashSet::new(), None).is_err());
+    }
+
+    #[test]
+    fn test_localized_title_and_description() {
+        let mut meta = sample_meta("p1");
+        meta.title = Some("Desktop".to_string());
+        meta.display_name = Some("Рабочий стол".to_string());
+        meta.description_ru = Some("Доступ ко всему".to_string());
+        meta.description_en = Some("  ".to_string());
+        assert_eq!(
+            localized_title(Some(&meta), None, Locale::Ru),
+            "Рабочий стол"
+        );
+        assert_eq!(localized_title(Some(&meta), None, Locale::En), "Desktop");
+        assert_eq!(
+            localized_description(Some(&meta), Locale::En),
+            "Доступ ко всему"
+        );
+
+        meta.title = None;
+        meta.description_en = Some("Access to everything".to_string());
+        assert_eq!(
+            localized_title(Some(&meta), None, Locale::En),
+            "Рабочий стол"
+        );
+        assert_eq!(
+            localized_description(Some(&meta), Locale::En),
+            "Access to everything"
+        );
+        assert_eq!(
+            localized_title(None, Some("Station"), Locale::En),
+            "Station"
+        );
+        assert_eq!(localized_title(None, None, Locale::Ru), "Игра");
+    }
+
+    #[test]
+    fn test_build_product_details() {
+        let mut meta = sample_meta("p1");
+        meta.description_ru = Some("Полное описание игры".to_string());
+        meta.description_en = Some("Full game description".to_string());
+        let card = Card {
+            product_id: "p1".to_string(),
+            title: "Game".to_string(),
+            alt: "Полное".to_string(),
+            age_group: "16+".to_string(),
+            page_picture: "https://example.com/page.jpg".to_string(),
+            ..Default::default()
+        };
+
+        let launch = resolve_launch("p1", &launch_table(""), &HashSet::new(), None);
+        let details = build_product_details(&card, Some(&meta), launch);
+        assert_eq!(details.description_ru, "Полное описание игры");
+        assert_eq!(details.description_en, "Full game description");
+        assert_eq!(details.age_group, "16+");
+        assert_eq!(details.page_picture, "https://example.com/page.jpg");
+        assert!(details.can_launch);
+        assert!(details.launch_error.is_none());
+
+        let launch = resolve_launch("p1", &HashMap::new(), &HashSet::new(), None);
+        let details = build_product_details(&card, None, launch);
+        assert!(details.description_ru.is_empty());
+        assert!(!details.can_launch);
+        assert_eq!(
+            details.launch_error.as_deref(),
+            Some("Не найдено описание запуска")
+        );
+    }
+
+    #[test]
+    fn test_resolve_launch_outside_allowed_paths() {
+        let mut table = launch_table("");
+        table.get_mut("p1").unwrap().allowed_paths = vec!["D:\\Games".to_string()];
+        assert!(resolve_launch("p1", &table, &HashSet::new(), None).is_err());
+
+        table.get_mut("p1").unwrap().allowed_paths = vec!["c:/".to_string()];
+        assert!(resolve_launch("p1", &table, &HashSet::new(), None).is_ok());
+    }
+
+    #[test]
+    fn test_is_path_allowed() {
+        let roots = vec!["C:\\Games\\".to_string(), "D:/Steam".to_string()];
+        assert!(is_path_allowed("C:\\Games\\Metro\\metro.exe", &roots));
+        assert!(is_path_allowed("d:\\steam\\steam.exe", &roots));
+        assert!(!is_path_allowed("C:\\GamesEvil\\x.exe", &roots));
+        assert!(!is_path_allowed("C:\\Games\\..\\Windows\\cmd.exe", &roots));
+        assert!(!is_path_allowed("C:\\Windows\\cmd.exe", &roots));
+        assert!(is_path_allowed("C:\\Windows\\cmd.exe", &[]));
+    }
+
+    #[test]
+    fn test_station_product_allowed_paths() {
+        let empty: StationProduct =
+            serde_json::from_str(r#"{"product_id": "p1", "allowed_paths": ""}"#).unwrap();
+        assert!(empty.allowed_paths.is_empty());
+
+        let joined: StationProduct = serde_json::from_str(
+            r#"{"product_id": "p1", "allowed_paths": "C:\\Games; D:\\Steam"}"#,
+        )
+        .unwrap();
+        assert_eq!(joined.allowed_paths, vec!["C:\\Games", "D:\\Steam"]);
+
+        let piped: StationProduct =
+            serde_json::from_str(r#"{"product_id": "p1", "allowed_paths": "C:\\A|B"}"#).unwrap();
+        assert_eq!(piped.allowed_paths, vec!["C:\\A|B"]);
+
+        let list: StationProduct =
+            serde_json::from_str(r#"{"product_id": "p1", "allowedPaths": ["C:\\Games"]}"#).unwrap();
+        assert_eq!(list.allowed_paths, vec!["C:\\Games"]);
+
+        let missing: StationProduct = serde_json::from_str(r#"{"product_id": "p1"}"#).unwrap();
+        assert!(missing.allowed_paths.is_empty());
+    }
+
+    #[test]
+    fn test_push_tail_line_keeps_last_lines() {
+        let mut tail = VecDeque::new();
+        for idx in 0..5 {
+            push_tail_line(&mut tail, format!("line {}", idx), 3);
+        }
+        assert_eq!(tail, vec!["line 2", "line 3", "line 4"]);
+    }
+
+    #[test]
+    fn test_drain_stderr_survives_invalid_utf8() {
+        // "Ошибка" in CP1251, then more output that must still be read.
+        let mut stderr = b"start\r\n\xce\xf8\xe8\xe1\xea\xe0\n".to_vec();
+        for idx in 0..3 {
+            stderr.extend_from_slice(format!("line {}\n", idx).as_bytes());
+        }
+        stderr.extend_from_slice(b"no newline");
+        let tail = Mutex::new(VecDeque::new());
+        drain_stderr(stderr.as_slice(), &tail, 10);
+
+        let tail = tail.into_inner().unwrap();
+        assert_eq!(tail.len(), 6);
+        assert_eq!(tail[0], "start");
+        assert_eq!(tail[1].chars().filter(|ch| *ch == '\u{FFFD}').count(), 6);
+        assert_eq!(tail[4], "line 2");
+        assert_eq!(tail[5], "no newline");
+    }
+
+    #[test]
+    fn test_take_due_waits_for_grace_period() {
+        let now = Instant::now();
+        let mut deferred = Some(DeferredPostExit {
+            product_id: "p1".to_string(),
+            hooks: Vec::new(),
+            env: Vec::new(),
+            not_before: now + Duration::from_secs(15),
+        });
+        assert!(take_due(&mut deferred, now).is_none());
+        assert!(deferred.is_some());
+
+        let due = take_due(&mut deferred, now + Duration::from_secs(15)).unwrap();
+        assert_eq!(due.product_id, "p1");
+        assert!(deferred.is_none());
+        assert!(take_due(&mut deferred, now + Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    fn test_push_recent_launch_moves_to_front() {
+        let mut recent = Vec::new();
+        for id in ["p1", "p2", "p3", "p1"] {
+            push_recent_launch(&mut recent, id, 2);
+        }
+        assert_eq!(recent, vec!["p1", "p3"]);
+    }
+
     #[test]
     fn test_is_desktop_product_ignores_use_default_desktop() {
         let mut item = sample_item("p1");
         item.use_default_desktop = Some(true);
         let mut meta = sample_meta("p1");
         meta.use_default_desktop = Some(true);
-        assert!(!is_desktop_product(&item, Some(&meta)));
+        assert!(!is_desktop_product(
+            &item,
+            Some(&meta),
+            &DesktopRules::default()
+        ));
+    }
+
+    #[test]
+    fn test_is_desktop_product_honors_use_default_desktop_when_enabled() {
+        let rules = DesktopRules {
+            use_default_desktop: true,
+            ..DesktopRules::default()
+        };
+        let mut item = sample_item("p1");
+        assert!(!is_desktop_product(&item, None, &rules));
+        item.use_default_desktop = Some(true);
+        assert!(is_desktop_product(&item, None, &rules));
+
+        let mut meta = sample_meta("p2");
+        meta.use_default_desktop = Some(true);
+        assert!(is_desktop_product(&sample_item("p2"), Some(&meta), &rules));
+    }
+
+    #[test]
+    fn test_is_desktop_product_custom_rules() {
+        let rules = DesktopRules {
+            product_ids: vec!["custom-desktop".to_string()],
+            titles: Vec::new(),
+            display_names: vec!["*стол*".to_string()],
+            use_default_desktop: false,
+        };
+        assert!(is_desktop_product(
+            &sample_item("custom-desktop"),
+            None,
+            &rules
+        ));
+
+        let mut titled = sample_item("p1");
+        titled.title = Some("Desktop".to_string());
+        assert!(!is_desktop_product(&titled, None, &rules));
+
+        let mut meta = sample_meta("p1");
+        meta.display_name = Some("Рабочий стол Windows".to_string());
+        assert!(is_desktop_product(&sample_item("p1"), Some(&meta), &rules));
     }
 
     #[test]
     fn test_is_desktop_product_by_id() {
         let item = sample_item(DESKTOP_PRODUCT_ID);
-        assert!(is_desktop_product(&item, None));
+        assert!(is_desktop_product(&item, None, &DesktopRules::default()));
     }
 
     #[test]
     fn test_is_desktop_product_by_title() {
         let mut item = sample_item("p1");
         item.title = Some("Desktop".to_string());
-        assert!(is_desktop_product(&item, None));
+        assert!(is_desktop_product(&item, None, &DesktopRules::default()));
     }
 
     #[test]
@@ -691,7 +2386,11 @@ mod tests {
         let item = sample_item("p1");
         let mut meta = sample_meta("p1");
         meta.display_name = Some("Рабочий стол".to_string());
-        assert!(is_desktop_product(&item, Some(&meta)));
+        assert!(is_desktop_product(
+            &item,
+            Some(&meta),
+            &DesktopRules::default()
+        ));
     }
 
     #[test]
@@ -724,7 +2423,7 @@ mod tests {
         meta.display_name = Some("Рабочий стол".to_string());
         let mut map = HashMap::new();
         map.insert("p1".to_string(), meta);
-        let set = build_desktop_set(&[item], &map);
+        let set = build_desktop_set(&[item], &map, &DesktopRules::default());
         assert!(set.contains("p1"));
     }
 
@@ -763,6 +2462,71 @@ mod tests {
         assert!(is_expired(old, Duration::from_secs(60)));
     }
 
+    #[test]
+    fn test_progress_percent() {
+        assert_eq!(progress_percent(Phase::Credentials, None, None), 0);
+        assert_eq!(progress_percent(Phase::Catalog, None, None), 15);
+        assert_eq!(progress_percent(Phase::Resources, Some(1), Some(2)), 65);
+        assert_eq!(progress_percent(Phase::Resources, Some(40), Some(40)), 100);
+        assert_eq!(progress_percent(Phase::Resources, Some(3), Some(0)), 30);
+    }
+
+    #[test]
+    fn test_status_payload_json() {
+        let value =
+            serde_json::to_value(StatusPayload::failed(Some(Phase::StationList), "HTTP 500"))
+                .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "text": "HTTP 500",
+                "phase": "failed",
+                "percent": 5,
+                "error": true,
+                "failedPhase": "stationList",
+                "current": null,
+                "total": null,
+            })
+        );
+        let value = serde_json::to_value(StatusPayload::progress(
+            Phase::Resources,
+            "",
+            Some(7),
+            Some(10),
+        ))
+        .unwrap();
+        assert_eq!(value["phase"], "resources");
+        assert_eq!(value["percent"], 79);
+        assert_eq!(value["error"], false);
+    }
+
+    #[test]
+    fn test_prune_cache_removes_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("drova-prune-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let now = SystemTime::now();
+        for (name, age_secs) in [("old.jpg", 300), ("mid.jpg", 200), ("new.jpg", 100)] {
+            let path = dir.join(name);
+            fs::write(&path, [0u8; 10]).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age_secs))
+                .unwrap();
+        }
+
+        // The file just written survives even when it is the oldest.
+        prune_cache(&dir, 15, &dir.join("old.jpg"));
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["old.jpg"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_build_product_map_overwrites_duplicates() {
         let mut first = sample_meta("p1");
@@ -775,9 +2539,26 @@ mod tests {
         assert_eq!(meta.title.as_deref(), Some("Second"));
     }
 
+    #[test]
+    fn test_product_meta_parses_catalog_fixture() {
+        let products: Vec<ProductMeta> =
+            serde_json::from_str(include_str!("../../fixtures/products_full.json")).unwrap();
+        let desktop = &products[0];
+        assert_eq!(desktop.publisher.as_deref(), Some("Valve"));
+        assert_eq!(desktop.developer.as_deref(), Some("Valve"));
+        assert_eq!(desktop.age_group.as_deref(), Some("16+"));
+        assert_eq!(desktop.license_type, None);
+        assert!(desktop.description_en.is_some());
+        assert!(desktop
+            .page_picture
+            .as_deref()
+            .unwrap()
+            .ends_with("pid-desktop.jpg"));
+    }
+
     #[test]
     fn test_station_products_url() {
-        let url = station_products_url("uuid-1");
+        let url = station_products_url(&ApiConfig::default(), "uuid-1");
         assert_eq!(
             url,
             "https://services.drova.io/product-manager/serverproduct/list/uuid-1"
@@ -786,7 +2567,7 @@ mod tests {
 
     #[test]
     fn test_station_info_url() {
-        let url = station_info_url("uuid-1");
+        let url = station_info_url(&ApiConfig::default(), "uuid-1");
         assert_eq!(
             url,
             "https://services.drova.io/server-manager/servers/public/uuid-1"
@@ -795,7 +2576,7 @@ mod tests {
 
     #[test]
     fn test_station_hardware_url() {
-        let url = station_hardware_url("uuid-1");
+        let url = station_hardware_url(&ApiConfig::default(), "uuid-1");
         assert_eq!(
             url,
             "https://services.drova.io/server-manager/hardware/list/uuid-1"
@@ -804,19 +2585,28 @@ mod tests {
 
     #[test]
     fn test_products_full_url() {
-        let url = products_full_url();
+        let url = products_full_url(&ApiConfig::default());
         assert_eq!(
             url,
             "https://services.drova.io/product-manager/product/listfull2?limit=2000"
         );
     }
 
+    #[test]
+    fn test_is_auth_failure() {
+        assert!(is_auth_failure(reqwest::StatusCode::UNAUTHORIZED));
+        assert!(is_auth_failure(reqwest::StatusCode::FORBIDDEN));
+        assert!(!is_auth_failure(reqwest::StatusCode::NOT_FOUND));
+        assert!(!is_auth_failure(reqwest::StatusCode::OK));
+        assert!(auth_error(reqwest::StatusCode::UNAUTHORIZED).contains("HTTP 401"));
+    }
+
     #[cfg(not(target_os = "windows"))]
     #[test]
     fn test_get_station_info_from_env() {
         std::env::set_var("DROVA_STATION_UUID", "uuid-env");
         std::env::set_var("DROVA_AUTH_TOKEN", "token-env");
-        let info = get_station_info().unwrap();
+        let info = get_station_info(&LocalConfig::default()).unwrap();
         assert_eq!(info.uuid, "uuid-env");
         assert_eq!(info.token, "token-env");
         std::env::remove_var("DROVA_STATION_UUID");
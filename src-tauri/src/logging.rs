@@ -0,0 +1,360 @@
+use crate::config::log_dir;
+use crate::StationInfo;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Deserialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LOG_FILE_NAME: &str = "launcher.log";
+/// The current file is rotated once it would grow past this size.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// The current file plus `launcher.1.log` … `launcher.4.log`.
+const KEPT_LOG_FILES: usize = 5;
+const STATION_PLACEHOLDER: &str = "[station]";
+const TOKEN_PLACEHOLDER: &str = "[token]";
+const AUTH_HEADER: &str = "x-auth-token";
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+/// Values that must never reach the log, with what is written instead.
+static SECRETS: Mutex<Vec<(String, &'static str)>> = Mutex::new(Vec::new());
+
+/// Writes to `launcher.log` in the app log directory (and to stderr in
+/// debug builds) from here on. Call as early as possible: messages logged
+/// before are dropped.
+pub fn init() {
+    let logger = LOGGER.get_or_init(|| FileLogger {
+        dir: log_dir(),
+        file: Mutex::new(None),
+    });
+    if log::set_logger(logger).is_ok() {
+        set_level(LogLevel::default());
+    }
+}
+
+/// Takes effect immediately; `logLevel` in the config is applied on every
+/// reload.
+pub fn set_level(level: LogLevel) {
+    log::set_max_level(level.filter());
+}
+
+/// Every later message has the station UUID and token replaced, including
+/// URLs that contain the UUID.
+pub fn hide_station(station: &StationInfo) {
+    hide_station_uuid(&station.uuid);
+    add_secret(&station.token, TOKEN_PLACEHOLDER);
+}
+
+pub fn hide_station_uuid(uuid: &str) {
+    add_secret(uuid, STATION_PLACEHOLDER);
+}
+
+fn add_secret(value: &str, placeholder: &'static str) {
+    let value = value.trim();
+    // Short values would blank out unrelated text.
+    if value.len() < 4 {
+        return;
+    }
+    if let Ok(mut secrets) = SECRETS.lock() {
+        if !secrets.iter().any(|(secret, _)| secret == value) {
+            secrets.push((value.to_string(), placeholder));
+        }
+    }
+}
+
+pub fn redact(message: &str) -> String {
+    let mut message = message.to_string();
+    if let Ok(secrets) = SECRETS.lock() {
+        for (secret, placeholder) in secrets.iter() {
+            if message.contains(secret.as_str()) {
+                message = message.replace(secret.as_str(), placeholder);
+            }
+        }
+    }
+    redact_auth_header(&message)
+}
+
+/// `X-Auth-Token: <value>` and `X-Auth-Token=<value>` in any case, for
+/// tokens that were never registered (e.g. echoed in a response body).
+fn redact_auth_header(message: &str) -> String {
+    // ASCII lowercasing keeps byte offsets in sync with `message`.
+    let lower = message.to_ascii_lowercase();
+    let mut result = String::with_capacity(message.len());
+    let mut rest = 0;
+    while let Some(found) = lower[rest..].find(AUTH_HEADER) {
+        let name_end = rest + found + AUTH_HEADER.len();
+        let after_name = &message[name_end..];
+        let separator =
+            after_name.len() - after_name.trim_start_matches([' ', '"', ':', '=']).len();
+        let value_start = name_end + separator;
+        let value_len = message[value_start..]
+            .find(|ch: char| ch.is_whitespace() || matches!(ch, '"' | ',' | ';' | '}'))
+            .unwrap_or(message.len() - value_start);
+        result.push_str(&message[rest..value_start]);
+        if value_len > 0 && after_name[..separator].contains([':', '=']) {
+            result.push_str(TOKEN_PLACEHOLDER);
+        } else {
+            result.push_str(&message[value_start..value_start + value_len]);
+        }
+        rest = value_start + value_len;
+    }
+    result.push_str(&message[rest..]);
+    result
+}
+
+struct FileLogger {
+    dir: Option<PathBuf>,
+    file: Mutex<Option<LogFile>>,
+}
+
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+impl Log for FileLogger {
+    /// Other crates (reqwest, tauri) only get through with warnings and
+    /// errors, so debug logging stays about the launcher.
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && (is_own_target(metadata.target()) || metadata.level() <= Level::Warn)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format_line(
+            SystemTime::now(),
+            record.level(),
+            record.target(),
+            &redact(&record.args().to_string()),
+        );
+        #[cfg(debug_assertions)]
+        eprint!("{}", line);
+        if let Some(dir) = &self.dir {
+            self.write(dir, &line);
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut slot) = self.file.lock() {
+            if let Some(log) = slot.as_mut() {
+                let _ = log.file.flush();
+            }
+        }
+    }
+}
+
+impl FileLogger {
+    /// A log that can't be written is silently skipped: there is nowhere
+    /// left to report it.
+    fn write(&self, dir: &Path, line: &str) {
+        let Ok(mut slot) = self.file.lock() else {
+            return;
+        };
+        let len = line.len() as u64;
+        if slot
+            .as_ref()
+            .is_some_and(|log| log.size > 0 && log.size + len > MAX_LOG_BYTES)
+        {
+            *slot = None;
+            rotate(dir);
+        }
+        if slot.is_none() {
+            *slot = open_log(dir);
+        }
+        if let Some(log) = slot.as_mut() {
+            if log.file.write_all(line.as_bytes()).is_ok() {
+                log.size += len;
+            }
+        }
+    }
+}
+
+/// The current log and the rotated ones that exist, newest first. Pending
+/// lines are flushed first.
+pub fn log_files() -> Vec<PathBuf> {
+    log::logger().flush();
+    let Some(dir) = log_dir() else {
+        return Vec::new();
+    };
+    std::iter::once(dir.join(LOG_FILE_NAME))
+        .chain((1..KEPT_LOG_FILES).map(|index| rotated_path(&dir, index)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn is_own_target(target: &str) -> bool {
+    let own = module_path!().split("::").next().unwrap_or_default();
+    target == own || target.starts_with(&format!("{}::", own))
+}
+
+fn open_log(dir: &Path) -> Option<LogFile> {
+    fs::create_dir_all(dir).ok()?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE_NAME))
+        .ok()?;
+    let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    Some(LogFile { file, size })
+}
+
+/// `launcher.log` becomes `launcher.1.log`, older files move up by one and
+/// the oldest is dropped.
+fn rotate(dir: &Path) {
+    let _ = fs::remove_file(rotated_path(dir, KEPT_LOG_FILES - 1));
+    for index in (1..KEPT_LOG_FILES - 1).rev() {
+        let _ = fs::rename(rotated_path(dir, index), rotated_path(dir, index + 1));
+    }
+    let _ = fs::rename(dir.join(LOG_FILE_NAME), rotated_path(dir, 1));
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("launcher.{}.log", index))
+}
+
+fn format_line(time: SystemTime, level: Level, target: &str, message: &str) -> String {
+    format!(
+        "{} {:<5} {}: {}\n",
+        format_timestamp(time),
+        level,
+        target,
+        message
+    )
+}
+
+/// RFC 3339 in UTC with milliseconds, e.g. `2024-03-01T12:00:00.250Z`.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's
+/// `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_timestamp() {
+        let at = |secs: u64, millis: u64| {
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis))
+        };
+        assert_eq!(at(0, 0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(at(951_782_400, 5), "2000-02-29T00:00:00.005Z");
+        assert_eq!(at(1_700_000_000, 250), "2023-11-14T22:13:20.250Z");
+    }
+
+    #[test]
+    fn test_redact_station_and_token() {
+        hide_station(&StationInfo {
+            uuid: "3f0c1a52-redact-test".to_string(),
+            token: "secret-token-redact-test".to_string(),
+        });
+        assert_eq!(
+            redact("HTTP GET https://x/list/3f0c1a52-redact-test token=secret-token-redact-test"),
+            "HTTP GET https://x/list/[station] token=[token]"
+        );
+    }
+
+    #[test]
+    fn test_redact_auth_header() {
+        assert_eq!(
+            redact_auth_header("headers: {\"X-Auth-Token\": \"abc123\", \"Accept\": \"*/*\"}"),
+            "headers: {\"X-Auth-Token\": \"[token]\", \"Accept\": \"*/*\"}"
+        );
+        assert_eq!(
+            redact_auth_header("x-auth-token=abc; next"),
+            "x-auth-token=[token]; next"
+        );
+        assert_eq!(
+            redact_auth_header("the X-Auth-Token header was rejected"),
+            "the X-Auth-Token header was rejected"
+        );
+    }
+
+    #[test]
+    fn test_rotate_keeps_newest_files() {
+        let dir = std::env::temp_dir().join(format!("drova-logs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for generation in 0..KEPT_LOG_FILES + 1 {
+            fs::write(dir.join(LOG_FILE_NAME), generation.to_string()).unwrap();
+            rotate(&dir);
+        }
+        assert!(!dir.join(LOG_FILE_NAME).exists());
+        assert_eq!(fs::read_to_string(rotated_path(&dir, 1)).unwrap(), "5");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&dir, KEPT_LOG_FILES - 1)).unwrap(),
+            "2"
+        );
+        assert!(!rotated_path(&dir, KEPT_LOG_FILES).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_level_serde() {
+        let level: LogLevel = serde_json::from_str("\"debug\"").unwrap();
+        assert_eq!(level.filter(), LevelFilter::Debug);
+        assert!(serde_json::from_str::<LogLevel>("\"verbose\"").is_err());
+    }
+}
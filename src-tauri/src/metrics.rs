@@ -0,0 +1,233 @@
+use crate::diagnostics::{self, push_capped, HttpRecord};
+use crate::logging;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+/// Loads kept for `get_metrics_history`.
+const HISTORY_MAX: usize = 20;
+
+/// The steps of a catalog load, in order.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    /// Station UUID and token from the credential providers (registry, env,
+    /// config).
+    Credentials,
+    StationList,
+    Catalog,
+    /// Card images.
+    Resources,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseMetrics {
+    pub phase: Phase,
+    pub elapsed_ms: u64,
+    /// API calls and image downloads; cached images are not counted.
+    pub requests: u64,
+    pub bytes: u64,
+}
+
+/// One catalog load, as sent in the `metrics` event.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadMetrics {
+    pub at: String,
+    /// Auto-refresh or the command line rather than `load_cards`.
+    pub background: bool,
+    pub total_ms: u64,
+    pub phases: Vec<PhaseMetrics>,
+    pub requests: Vec<HttpRecord>,
+    pub error: Option<String>,
+}
+
+static HISTORY: Mutex<VecDeque<LoadMetrics>> = Mutex::new(VecDeque::new());
+static DOWNLOADS: AtomicU64 = AtomicU64::new(0);
+static DOWNLOADED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Counts an image fetched into the cache.
+pub fn record_download(bytes: usize) {
+    DOWNLOADS.fetch_add(1, Ordering::Relaxed);
+    DOWNLOADED_BYTES.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+/// Oldest first.
+pub fn history() -> Vec<LoadMetrics> {
+    HISTORY
+        .lock()
+        .map(|history| history.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Request and download counters at one moment. Requests are told apart
+/// only by time, so a background refresh overlapping `load_cards` would
+/// show up in both.
+#[derive(Clone, Copy)]
+struct Counters {
+    http_id: u64,
+    downloads: u64,
+    downloaded_bytes: u64,
+}
+
+impl Counters {
+    fn now() -> Self {
+        Self {
+            http_id: diagnostics::next_http_id(),
+            downloads: DOWNLOADS.load(Ordering::Relaxed),
+            downloaded_bytes: DOWNLOADED_BYTES.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Times the phases of one load. Each `phase` call ends the previous one.
+pub struct Timeline {
+    at: SystemTime,
+    background: bool,
+    started: Instant,
+    start: Counters,
+    current: Option<(Phase, Instant, Counters)>,
+    phases: Vec<(Phase, u64, Counters, Counters)>,
+}
+
+impl Timeline {
+    pub fn start(background: bool) -> Self {
+        Self {
+            at: SystemTime::now(),
+            background,
+            started: Instant::now(),
+            start: Counters::now(),
+            current: None,
+            phases: Vec::new(),
+        }
+    }
+
+    pub fn phase(&mut self, phase: Phase) {
+        self.end_phase();
+        self.current = Some((phase, Instant::now(), Counters::now()));
+    }
+
+    /// The phase in progress, or the last one once a load has stopped.
+    pub fn current_phase(&self) -> Option<Phase> {
+        self.current.map(|(phase, _, _)| phase)
+    }
+
+    fn end_phase(&mut self) {
+        if let Some((phase, started, from)) = self.current.take() {
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            self.phases.push((phase, elapsed_ms, from, Counters::now()));
+        }
+    }
+
+    /// Ends the load, adds it to the history and returns it.
+    pub fn finish(mut self, error: Option<&String>) -> LoadMetrics {
+        self.end_phase();
+        let requests = diagnostics::http_since(self.start.http_id);
+        let phases = self
+            .phases
+            .iter()
+            .map(|&(phase, elapsed_ms, from, to)| {
+                phase_metrics(phase, elapsed_ms, from, to, &requests)
+            })
+            .collect();
+        let metrics = LoadMetrics {
+            at: logging::format_timestamp(self.at),
+            background: self.background,
+            total_ms: self.started.elapsed().as_millis() as u64,
+            phases,
+            requests,
+            error: error.cloned(),
+        };
+        log::info!(
+            "Catalog loaded in {} ms: {}",
+            metrics.total_ms,
+            metrics
+                .phases
+                .iter()
+                .map(|phase| format!(
+                    "{:?} {} ms, {} B",
+                    phase.phase, phase.elapsed_ms, phase.bytes
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if let Ok(mut history) = HISTORY.lock() {
+            push_capped(&mut history, metrics.clone(), HISTORY_MAX);
+        }
+        metrics
+    }
+}
+
+fn phase_metrics(
+    phase: Phase,
+    elapsed_ms: u64,
+    from: Counters,
+    to: Counters,
+    requests: &[HttpRecord],
+) -> PhaseMetrics {
+    let api: Vec<&HttpRecord> = requests
+        .iter()
+        .filter(|record| (from.http_id..to.http_id).contains(&record.id))
+        .collect();
+    PhaseMetrics {
+        phase,
+        elapsed_ms,
+        requests: api.len() as u64 + (to.downloads - from.downloads),
+        bytes: api.iter().filter_map(|record| record.bytes).sum::<u64>()
+            + (to.downloaded_bytes - from.downloaded_bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: u64, bytes: Option<u64>) -> HttpRecord {
+        HttpRecord {
+            id,
+            at: String::new(),
+            url: format!("https://example.test/{}", id),
+            status: bytes.map(|_| 200),
+            elapsed_ms: 1,
+            bytes,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_phase_metrics_counts_requests_in_range() {
+        let requests = [record(4, Some(100)), record(5, None), record(6, Some(7))];
+        let from = Counters {
+            http_id: 4,
+            downloads: 10,
+            downloaded_bytes: 1000,
+        };
+        let to = Counters {
+            http_id: 6,
+            downloads: 12,
+            downloaded_bytes: 1500,
+        };
+
+        let metrics = phase_metrics(Phase::Resources, 30, from, to, &requests);
+        assert_eq!(
+            metrics,
+            PhaseMetrics {
+                phase: Phase::Resources,
+                elapsed_ms: 30,
+                requests: 4,
+                bytes: 600,
+            }
+        );
+    }
+
+    #[test]
+    fn test_phase_serializes_camel_case() {
+        assert_eq!(
+            serde_json::to_string(&Phase::StationList).unwrap(),
+            "\"stationList\""
+        );
+    }
+}
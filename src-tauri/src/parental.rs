@@ -0,0 +1,310 @@
+use crate::i18n::{tr, tr_fmt};
+use crate::{Card, DESKTOP_ALIAS};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+const PIN_HASH_SCHEME: &str = "pbkdf2-sha256";
+/// A PIN has few digits, so every guess has to cost real time.
+const PIN_HASH_ITERATIONS: u32 = 200_000;
+const PIN_SALT_LEN: usize = 16;
+/// Wrong PINs in a row before the first lockout.
+const FREE_PIN_ATTEMPTS: u32 = 3;
+/// Doubles with every further wrong PIN, up to `PIN_LOCKOUT_MAX`.
+const PIN_LOCKOUT: Duration = Duration::from_secs(30);
+const PIN_LOCKOUT_MAX: Duration = Duration::from_secs(15 * 60);
+
+/// The restriction in force while parental mode is on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AgeLimit {
+    pub max_age: u8,
+    /// Most of the catalog has no `ageGroup`, so unrated games stay
+    /// visible unless the owner asks otherwise.
+    pub hide_unrated: bool,
+}
+
+impl AgeLimit {
+    pub fn allows(&self, age_group: &str) -> bool {
+        match min_age(age_group) {
+            Some(age) => age <= self.max_age,
+            None => !self.hide_unrated,
+        }
+    }
+}
+
+/// Minimum age from a catalog `ageGroup` such as `16+`.
+fn min_age(age_group: &str) -> Option<u8> {
+    let digits: String = age_group
+        .trim()
+        .chars()
+        .take_while(|ch| ch.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+pub fn is_allowed(card: &Card, limit: Option<AgeLimit>) -> bool {
+    limit.is_none_or(|limit| limit.allows(&card.age_group))
+}
+
+/// Guards launches by product ID, so a hidden game can't be started by
+/// invoking the command directly. The `desktop` alias is checked against
+/// every Desktop product of the station; IDs that are not in the catalog
+/// are refused while a limit is on.
+pub fn check_launch(
+    cards: &[Card],
+    desktop_ids: &HashSet<String>,
+    product_id: &str,
+    limit: Option<AgeLimit>,
+) -> Result<(), String> {
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+    let targets: Vec<&Card> = cards
+        .iter()
+        .filter(|card| {
+            card.product_id == product_id
+                || (product_id == DESKTOP_ALIAS && desktop_ids.contains(&card.product_id))
+        })
+        .collect();
+    if !targets.is_empty() && targets.iter().all(|card| limit.allows(&card.age_group)) {
+        Ok(())
+    } else {
+        Err(tr("Игра недоступна в режиме родительского контроля").to_string())
+    }
+}
+
+/// `pbkdf2-sha256$<iterations>$<salt>$<hash>` with a random salt, as
+/// `--hash-pin` prints it for `pinHash`.
+pub fn hash_pin(pin: &str) -> Result<String, String> {
+    let mut salt = [0u8; PIN_SALT_LEN];
+    getrandom::getrandom(&mut salt).map_err(|err| err.to_string())?;
+    Ok(hash_pin_with(pin, &salt, PIN_HASH_ITERATIONS))
+}
+
+fn hash_pin_with(pin: &str, salt: &[u8], iterations: u32) -> String {
+    format!(
+        "{}${}${}${}",
+        PIN_HASH_SCHEME,
+        iterations,
+        hex::encode(salt),
+        hex::encode(derive_pin_key(pin, salt, iterations))
+    )
+}
+
+/// A cheap hash for tests elsewhere in the crate.
+#[cfg(test)]
+pub fn test_pin_hash(pin: &str) -> String {
+    hash_pin_with(pin, b"test-salt", 1)
+}
+
+fn derive_pin_key(pin: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(pin.trim().as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+struct PinHash {
+    iterations: u32,
+    salt: Vec<u8>,
+    key: Vec<u8>,
+}
+
+fn parse_pin_hash(value: &str) -> Option<PinHash> {
+    let mut parts = value.trim().split('$');
+    if parts.next()? != PIN_HASH_SCHEME {
+        return None;
+    }
+    let iterations = parts.next()?.parse().ok().filter(|count| *count > 0)?;
+    let salt = hex::decode(parts.next()?)
+        .ok()
+        .filter(|salt| !salt.is_empty())?;
+    let key = hex::decode(parts.next()?)
+        .ok()
+        .filter(|key| key.len() == 32)?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(PinHash {
+        iterations,
+        salt,
+        key,
+    })
+}
+
+pub fn is_pin_hash(value: &str) -> bool {
+    parse_pin_hash(value).is_some()
+}
+
+/// Without a configured hash there is no PIN that matches.
+pub fn verify_pin(pin: &str, expected: Option<&str>) -> bool {
+    let Some(expected) = expected.and_then(parse_pin_hash) else {
+        return false;
+    };
+    let key = derive_pin_key(pin, &expected.salt, expected.iterations);
+    // Compares every byte, so the time taken says nothing about the match.
+    key.iter()
+        .zip(&expected.key)
+        .fold(0, |diff, (left, right)| diff | (left ^ right))
+        == 0
+}
+
+/// Wrong owner PINs in a row. One counter covers parental unlock and
+/// kiosk exit, so neither prompt can be used to guess for the other.
+#[derive(Default, Debug)]
+pub struct PinAttempts {
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+impl PinAttempts {
+    /// Checks `pin` unless a lockout is running. After
+    /// `FREE_PIN_ATTEMPTS` wrong PINs each further one locks the prompt,
+    /// twice as long every time.
+    pub fn verify(
+        &mut self,
+        pin: &str,
+        expected: Option<&str>,
+        now: Instant,
+    ) -> Result<(), String> {
+        if let Some(until) = self.locked_until.filter(|until| *until > now) {
+            let secs = (until - now).as_secs_f64().ceil() as u64;
+            return Err(tr_fmt(
+                "Слишком много неверных попыток. Повторите через {} с",
+                &[&secs],
+            ));
+        }
+        if verify_pin(pin, expected) {
+            *self = Self::default();
+            return Ok(());
+        }
+        self.failures += 1;
+        if self.failures >= FREE_PIN_ATTEMPTS {
+            let doublings = (self.failures - FREE_PIN_ATTEMPTS).min(10);
+            let lockout = (PIN_LOCKOUT * 2u32.pow(doublings)).min(PIN_LOCKOUT_MAX);
+            self.locked_until = Some(now + lockout);
+        }
+        Err(tr("Неверный PIN").to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(product_id: &str, age_group: &str) -> Card {
+        Card {
+            product_id: product_id.to_string(),
+            age_group: age_group.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_age_limit_allows() {
+        let limit = AgeLimit {
+            max_age: 12,
+            hide_unrated: false,
+        };
+        assert!(limit.allows("12+"));
+        assert!(limit.allows(" 6+ "));
+        assert!(!limit.allows("16+"));
+        assert!(limit.allows(""));
+
+        let strict = AgeLimit {
+            hide_unrated: true,
+            ..limit
+        };
+        assert!(!strict.allows(""));
+        assert!(!strict.allows("для всех"));
+        assert!(strict.allows("0+"));
+    }
+
+    #[test]
+    fn test_check_launch() {
+        let cards = vec![card("kids", "6+"), card("shooter", "18+"), card("free", "")];
+        let none = HashSet::new();
+        let limit = Some(AgeLimit {
+            max_age: 12,
+            hide_unrated: false,
+        });
+        assert!(check_launch(&cards, &none, "kids", limit).is_ok());
+        assert!(check_launch(&cards, &none, "free", limit).is_ok());
+        assert!(check_launch(&cards, &none, "shooter", limit).is_err());
+        assert!(check_launch(&cards, &none, "unknown", limit).is_err());
+        assert!(check_launch(&cards, &none, "shooter", None).is_ok());
+        assert!(check_launch(&cards, &none, "unknown", None).is_ok());
+    }
+
+    #[test]
+    fn test_check_launch_desktop_alias() {
+        let cards = vec![card("pid-desktop", "16+"), card("kids", "6+")];
+        let desktop_ids = HashSet::from(["pid-desktop".to_string()]);
+        let limit = Some(AgeLimit {
+            max_age: 12,
+            hide_unrated: false,
+        });
+        assert!(check_launch(&cards, &desktop_ids, DESKTOP_ALIAS, limit).is_err());
+        assert!(check_launch(&cards, &desktop_ids, "pid-desktop", limit).is_err());
+        assert!(check_launch(&[], &HashSet::new(), DESKTOP_ALIAS, limit).is_err());
+
+        let adult = Some(AgeLimit {
+            max_age: 18,
+            hide_unrated: false,
+        });
+        assert!(check_launch(&cards, &desktop_ids, DESKTOP_ALIAS, adult).is_ok());
+        assert!(check_launch(&cards, &desktop_ids, DESKTOP_ALIAS, None).is_ok());
+    }
+
+    #[test]
+    fn test_verify_pin() {
+        let hash = hash_pin_with("1234", b"salt", 10);
+        assert!(hash.starts_with("pbkdf2-sha256$10$73616c74$"));
+        assert!(is_pin_hash(&hash));
+        assert!(verify_pin("1234", Some(&hash)));
+        assert!(verify_pin(" 1234 ", Some(&hash)));
+        assert!(!verify_pin("4321", Some(&hash)));
+        assert!(!verify_pin("1234", None));
+        assert_ne!(hash, hash_pin_with("1234", b"pepper", 10));
+
+        // Plain SHA-256, as older configs had it, is no longer accepted.
+        let sha256 = "03ac674216f3e15c761ee1a5e255f067953623c8b388b4459e13f978d7c846f4";
+        assert!(!is_pin_hash(sha256));
+        assert!(!verify_pin("1234", Some(sha256)));
+        assert!(!is_pin_hash("pbkdf2-sha256$0$73616c74$00"));
+    }
+
+    #[test]
+    fn test_hash_pin_format() {
+        let hash = hash_pin("1234").unwrap();
+        assert!(hash.starts_with("pbkdf2-sha256$200000$"));
+        assert_eq!(parse_pin_hash(&hash).unwrap().salt.len(), PIN_SALT_LEN);
+    }
+
+    #[test]
+    fn test_pin_attempts_lock_out() {
+        let hash = Some(test_pin_hash("1234"));
+        let expected = hash.as_deref();
+        let start = Instant::now();
+        let mut attempts = PinAttempts::default();
+
+        for _ in 0..FREE_PIN_ATTEMPTS {
+            assert_eq!(
+                attempts.verify("0000", expected, start).unwrap_err(),
+                "Неверный PIN"
+            );
+        }
+        // Locked: even the right PIN is not checked.
+        let locked = attempts.verify("1234", expected, start).unwrap_err();
+        assert!(locked.contains("30"), "{}", locked);
+
+        let later = start + PIN_LOCKOUT;
+        assert!(attempts.verify("0000", expected, later).is_err());
+        let locked = attempts.verify("1234", expected, later).unwrap_err();
+        assert!(locked.contains("60"), "{}", locked);
+
+        let much_later = later + PIN_LOCKOUT_MAX;
+        assert!(attempts.verify("1234", expected, much_later).is_ok());
+        assert!(attempts.verify("0000", expected, much_later).is_err());
+        assert!(attempts.locked_until.is_none());
+    }
+}
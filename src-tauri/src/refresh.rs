@@ -0,0 +1,164 @@
+use crate::parental::is_allowed;
+use crate::{
+    arrange_cards, checked_config, current_age_limit, current_config, current_favorites,
+    current_sort_mode, fetch_catalog, record_seen, Card, SharedState,
+};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the loop looks at `autoRefreshSecs`, so that a changed or
+/// re-enabled interval applies without a restart.
+const REFRESH_TICK: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct CardsDiff {
+    added: Vec<Card>,
+    /// Product IDs of cards that are no longer on the station.
+    removed: Vec<String>,
+    updated: Vec<Card>,
+    /// Product IDs of every card the UI shows, in display order, so that
+    /// added cards land where pinned games, favorites and the sort mode
+    /// put them.
+    order: Vec<String>,
+}
+
+impl CardsDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// Re-fetches the game list on the interval from the local config and
+/// emits `cards_changed` when it differs from what the UI shows. Running
+/// games are untouched: only the catalog used for new launches changes.
+/// The interval is read from the current config on every tick.
+pub fn start_auto_refresh(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last = Instant::now();
+        loop {
+            std::thread::sleep(REFRESH_TICK);
+            let interval = current_config(&app.state::<SharedState>()).auto_refresh_interval();
+            if interval.is_none() {
+                // Re-enabling waits a full interval rather than firing at once.
+                last = Instant::now();
+            }
+            if !refresh_due(interval, last.elapsed()) {
+                continue;
+            }
+            last = Instant::now();
+            if let Err(err) = tauri::async_runtime::block_on(refresh_cards(&app)) {
+                log::warn!("Background refresh failed: {}", err);
+            }
+        }
+    });
+}
+
+/// `None` is `autoRefreshSecs: 0`.
+fn refresh_due(interval: Option<Duration>, since_last: Duration) -> bool {
+    interval.is_some_and(|interval| since_last >= interval)
+}
+
+async fn refresh_cards(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<SharedState>();
+    // Until the first successful `load_cards` the UI shows an error or a
+    // spinner, and a retry loads everything anyway.
+    if state
+        .current_station
+        .lock()
+        .map_err(|_| "State locked")?
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let local_config = checked_config(&state)?;
+    let (catalog, station) = fetch_catalog(None, &local_config).await?;
+    record_seen(&state, &catalog.cards);
+    let favorites = current_favorites(&state);
+    let age_limit = current_age_limit(&state);
+    // Only what the UI can show: hidden games are neither added nor removed.
+    let visible = |cards: &[Card]| -> Vec<Card> {
+        let cards: Vec<Card> = cards
+            .iter()
+            .filter(|card| is_allowed(card, age_limit))
+            .cloned()
+            .collect();
+        favorites.apply(&cards)
+    };
+    let arranged = arrange_cards(
+        &catalog.cards,
+        &favorites,
+        age_limit,
+        current_sort_mode(&state),
+    );
+    let mut diff = {
+        let mut current = state.catalog.lock().map_err(|_| "State locked")?;
+        let diff = diff_cards(&visible(&current.cards), &arranged);
+        *current = catalog;
+        diff
+    };
+    diff.order = arranged.into_iter().map(|card| card.product_id).collect();
+    *state.current_station.lock().map_err(|_| "State locked")? = Some(station);
+
+    if !diff.is_empty() {
+        log::info!(
+            "Cards changed: {} added, {} removed, {} updated",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.updated.len()
+        );
+        let _ = app.emit("cards_changed", diff);
+    }
+    Ok(())
+}
+
+fn diff_cards(old: &[Card], new: &[Card]) -> CardsDiff {
+    let mut diff = CardsDiff::default();
+    for card in new {
+        match old.iter().find(|prev| prev.product_id == card.product_id) {
+            None => diff.added.push(card.clone()),
+            Some(prev) if prev != card => diff.updated.push(card.clone()),
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|prev| !new.iter().any(|card| card.product_id == prev.product_id))
+        .map(|prev| prev.product_id.clone())
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(product_id: &str, title: &str) -> Card {
+        Card {
+            product_id: product_id.to_string(),
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_cards() {
+        let old = vec![card("a", "A"), card("b", "B"), card("c", "C")];
+        let new = vec![card("a", "A"), card("b", "B2"), card("d", "D")];
+        let diff = diff_cards(&old, &new);
+        assert_eq!(diff.added, vec![card("d", "D")]);
+        assert_eq!(diff.removed, vec!["c".to_string()]);
+        assert_eq!(diff.updated, vec![card("b", "B2")]);
+        assert!(diff_cards(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_refresh_due() {
+        let minute = Duration::from_secs(60);
+        assert!(!refresh_due(None, minute));
+        assert!(!refresh_due(Some(minute), Duration::from_secs(59)));
+        assert!(refresh_due(Some(minute), minute));
+    }
+}
@@ -0,0 +1,411 @@
+/// Searchable text of one card. Names rank above the description.
+#[derive(Clone, Debug, Default)]
+pub struct SearchEntry {
+    pub product_id: String,
+    pub names: Vec<String>,
+    pub description: String,
+}
+
+#[derive(Debug, Default)]
+struct IndexedText {
+    /// Normalized text plus its Latin transliteration.
+    forms: Vec<Text>,
+}
+
+#[derive(Debug, Default)]
+struct Text {
+    joined: String,
+    words: Vec<String>,
+}
+
+#[derive(Debug)]
+struct IndexedEntry {
+    product_id: String,
+    names: Vec<IndexedText>,
+    description: IndexedText,
+}
+
+/// Built with the catalog, so a background refresh swaps it together with
+/// the cards.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    entries: Vec<IndexedEntry>,
+}
+
+const SCORE_PREFIX: u32 = 100;
+const SCORE_SUBSTRING: u32 = 80;
+const SCORE_WORDS: u32 = 60;
+const SCORE_FUZZY: u32 = 40;
+/// Description hits rank below any name hit.
+const DESCRIPTION_DIVISOR: u32 = 4;
+
+impl SearchIndex {
+    pub fn new(entries: Vec<SearchEntry>) -> Self {
+        Self {
+            entries: entries
+                .into_iter()
+                .map(|entry| IndexedEntry {
+                    product_id: entry.product_id,
+                    names: entry
+                        .names
+                        .iter()
+                        .filter(|name| !name.trim().is_empty())
+                        .map(|name| index_text(name))
+                        .collect(),
+                    description: index_text(&entry.description),
+                })
+                .collect(),
+        }
+    }
+
+    /// Product IDs matching `query`, best first. Ties keep catalog order.
+    pub fn search(&self, query: &str) -> Vec<String> {
+        let variants = query_variants(query);
+        if variants.is_empty() {
+            return self
+                .entries
+                .iter()
+                .map(|entry| entry.product_id.clone())
+                .collect();
+        }
+
+        let mut scored: Vec<(u32, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| {
+                let score = variants
+                    .iter()
+                    .map(|query| score_entry(entry, query))
+                    .max()
+                    .unwrap_or(0);
+                (score > 0).then_some((score, idx))
+            })
+            .collect();
+        scored.sort_by(|left, right| right.0.cmp(&left.0).then(left.1.cmp(&right.1)));
+        scored
+            .into_iter()
+            .map(|(_, idx)| self.entries[idx].product_id.clone())
+            .collect()
+    }
+}
+
+fn score_entry(entry: &IndexedEntry, query: &Text) -> u32 {
+    let name_score = entry
+        .names
+        .iter()
+        .map(|name| score_text(name, query, true))
+        .max()
+        .unwrap_or(0);
+    let description_score = score_text(&entry.description, query, false) / DESCRIPTION_DIVISOR;
+    name_score.max(description_score)
+}
+
+fn score_text(text: &IndexedText, query: &Text, fuzzy: bool) -> u32 {
+    text.forms
+        .iter()
+        .map(|form| score_form(form, query, fuzzy))
+        .max()
+        .unwrap_or(0)
+}
+
+fn score_form(form: &Text, query: &Text, fuzzy: bool) -> u32 {
+    if form.joined.starts_with(&query.joined) {
+        return SCORE_PREFIX;
+    }
+    if form.joined.contains(&query.joined) {
+        return SCORE_SUBSTRING;
+    }
+
+    let mut used_fuzzy = false;
+    for token in &query.words {
+        if form
+            .words
+            .iter()
+            .any(|word| word.starts_with(token.as_str()))
+        {
+            continue;
+        }
+        if fuzzy && form.words.iter().any(|word| fuzzy_word_match(token, word)) {
+            used_fuzzy = true;
+            continue;
+        }
+        return 0;
+    }
+    if used_fuzzy {
+        SCORE_FUZZY
+    } else {
+        SCORE_WORDS
+    }
+}
+
+/// Allows one typo in words of 4–7 letters and two in longer ones, also
+/// against the start of a longer word so partial input still matches.
+fn fuzzy_word_match(token: &str, word: &str) -> bool {
+    let token: Vec<char> = token.chars().collect();
+    let allowed = match token.len() {
+        0..=3 => return false,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let word: Vec<char> = word.chars().collect();
+    if edit_distance(&token, &word) <= allowed {
+        return true;
+    }
+    let prefix_len = (token.len() + 1).min(word.len());
+    word.len() > token.len() && edit_distance(&token, &word[..prefix_len]) <= allowed
+}
+
+/// Optimal string alignment distance: Levenshtein plus swapped neighbours.
+fn edit_distance(left: &[char], right: &[char]) -> usize {
+    let width = right.len() + 1;
+    let mut rows = vec![0usize; (left.len() + 1) * width];
+    for (j, cell) in rows.iter_mut().enumerate().take(width) {
+        *cell = j;
+    }
+    for i in 1..=left.len() {
+        rows[i * width] = i;
+        for j in 1..=right.len() {
+            let cost = usize::from(left[i - 1] != right[j - 1]);
+            let mut best = (rows[(i - 1) * width + j] + 1)
+                .min(rows[i * width + j - 1] + 1)
+                .min(rows[(i - 1) * width + j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                best = best.min(rows[(i - 2) * width + j - 2] + 1);
+            }
+            rows[i * width + j] = best;
+        }
+    }
+    rows[left.len() * width + right.len()]
+}
+
+fn index_text(value: &str) -> IndexedText {
+    let normalized = normalize(value);
+    if normalized.is_empty() {
+        return IndexedText::default();
+    }
+    let transliterated = transliterate(&normalized);
+    let mut forms = vec![to_text(normalized.clone())];
+    if transliterated != normalized {
+        forms.push(to_text(transliterated));
+    }
+    IndexedText { forms }
+}
+
+/// The query as typed, as if typed in the other keyboard layout, and the
+/// Latin transliteration of both.
+fn query_variants(query: &str) -> Vec<Text> {
+    let lowered = query.to_lowercase();
+    let mut variants: Vec<String> = Vec::new();
+    for candidate in [lowered.clone(), swap_layout(&lowered)] {
+        let normalized = normalize(&candidate);
+        if normalized.is_empty() {
+            continue;
+        }
+        let transliterated = transliterate(&normalized);
+        for variant in [normalized, transliterated] {
+            if !variants.contains(&variant) {
+                variants.push(variant);
+            }
+        }
+    }
+    variants.into_iter().map(to_text).collect()
+}
+
+fn to_text(joined: String) -> Text {
+    let words = joined.split(' ').map(str::to_string).collect();
+    Text { joined, words }
+}
+
+/// Lowercase letters and digits separated by single spaces; `ё` is `е`.
+fn normalize(value: &str) -> String {
+    let mut result = String::new();
+    let mut pending_space = false;
+    for ch in value.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() {
+            if pending_space && !result.is_empty() {
+                result.push(' ');
+            }
+            pending_space = false;
+            result.push(if ch == 'ё' { 'е' } else { ch });
+        } else {
+            pending_space = true;
+        }
+    }
+    result
+}
+
+fn transliterate(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    for ch in value.chars() {
+        let latin = match ch {
+            'а' => "a",
+            'б' => "b",
+            'в' => "v",
+            'г' => "g",
+            'д' => "d",
+            'е' | 'э' => "e",
+            'ж' => "zh",
+            'з' => "z",
+            'и' => "i",
+            'й' | 'ы' => "y",
+            'к' => "k",
+            'л' => "l",
+            'м' => "m",
+            'н' => "n",
+            'о' => "o",
+            'п' => "p",
+            'р' => "r",
+            'с' => "s",
+            'т' => "t",
+            'у' => "u",
+            'ф' => "f",
+            'х' => "h",
+            'ц' => "c",
+            'ч' => "ch",
+            'ш' => "sh",
+            'щ' => "sch",
+            'ъ' | 'ь' => "",
+            'ю' => "yu",
+            'я' => "ya",
+            _ => {
+                result.push(ch);
+                continue;
+            }
+        };
+        result.push_str(latin);
+    }
+    result
+}
+
+const LAYOUT_PAIRS: &[(char, char)] = &[
+    ('q', 'й'),
+    ('w', 'ц'),
+    ('e', 'у'),
+    ('r', 'к'),
+    ('t', 'е'),
+    ('y', 'н'),
+    ('u', 'г'),
+    ('i', 'ш'),
+    ('o', 'щ'),
+    ('p', 'з'),
+    ('[', 'х'),
+    (']', 'ъ'),
+    ('a', 'ф'),
+    ('s', 'ы'),
+    ('d', 'в'),
+    ('f', 'а'),
+    ('g', 'п'),
+    ('h', 'р'),
+    ('j', 'о'),
+    ('k', 'л'),
+    ('l', 'д'),
+    (';', 'ж'),
+    ('\'', 'э'),
+    ('z', 'я'),
+    ('x', 'ч'),
+    ('c', 'с'),
+    ('v', 'м'),
+    ('b', 'и'),
+    ('n', 'т'),
+    ('m', 'ь'),
+    (',', 'б'),
+    ('.', 'ю'),
+    ('`', 'ё'),
+];
+
+/// Maps keys between the QWERTY and ЙЦУКЕН layouts in both directions.
+fn swap_layout(value: &str) -> String {
+    value
+        .chars()
+        .map(|ch| {
+            LAYOUT_PAIRS
+                .iter()
+                .find_map(|&(latin, cyrillic)| {
+                    if ch == latin {
+                        Some(cyrillic)
+                    } else if ch == cyrillic {
+                        Some(latin)
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or(ch)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(product_id: &str, names: &[&str], description: &str) -> SearchEntry {
+        SearchEntry {
+            product_id: product_id.to_string(),
+            names: names.iter().map(|name| name.to_string()).collect(),
+            description: description.to_string(),
+        }
+    }
+
+    fn index() -> SearchIndex {
+        SearchIndex::new(vec![
+            entry(
+                "witcher",
+                &["The Witcher 3: Wild Hunt"],
+                "Ведьмак Геральт из Ривии ищет Цири.",
+            ),
+            entry("cyberpunk", &["Cyberpunk 2077"], "Найт-Сити ждёт."),
+            entry("stalker", &["S.T.A.L.K.E.R. 2", "Сталкер 2"], ""),
+            entry("desktop", &["Desktop", "Рабочий стол"], ""),
+        ])
+    }
+
+    #[test]
+    fn test_search_by_description_word() {
+        assert_eq!(index().search("ведьмак"), vec!["witcher"]);
+    }
+
+    #[test]
+    fn test_search_wrong_keyboard_layout() {
+        assert_eq!(index().search("сниукзгтл"), vec!["cyberpunk"]);
+        assert_eq!(index().search("hf,jxbq"), vec!["desktop"]);
+    }
+
+    #[test]
+    fn test_search_transliteration() {
+        assert_eq!(index().search("stalker"), vec!["stalker"]);
+        assert_eq!(index().search("rabochiy"), vec!["desktop"]);
+    }
+
+    #[test]
+    fn test_search_typos() {
+        assert_eq!(index().search("wicher"), vec!["witcher"]);
+        assert_eq!(index().search("cyberpnuk"), vec!["cyberpunk"]);
+        assert!(index().search("xyz").is_empty());
+    }
+
+    #[test]
+    fn test_search_empty_query_keeps_order() {
+        assert_eq!(
+            index().search("  "),
+            vec!["witcher", "cyberpunk", "stalker", "desktop"]
+        );
+    }
+
+    #[test]
+    fn test_search_ranks_names_above_description() {
+        let index = SearchIndex::new(vec![
+            entry("a", &["Hunt Showdown"], ""),
+            entry("b", &["Other"], "hunt"),
+        ]);
+        assert_eq!(index.search("hunt"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_normalize_and_swap_layout() {
+        assert_eq!(normalize("  S.T.A.L.K.E.R.  2 "), "s t a l k e r 2");
+        assert_eq!(normalize("Ёлки"), "елки");
+        assert_eq!(swap_layout("ghbdtn"), "привет");
+        assert_eq!(swap_layout("руддщ"), "hello");
+    }
+}
@@ -0,0 +1,298 @@
+use crate::config::data_dir;
+use crate::i18n::tr;
+use crate::Card;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+const PREFERENCES_FILE_NAME: &str = "preferences.json";
+const PLAY_STATS_FILE_NAME: &str = "play_stats.json";
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SortMode {
+    /// Whatever order the station product list uses.
+    #[default]
+    Station,
+    Alphabetical,
+    MostPlayed,
+    RecentlyAdded,
+    FreeFirst,
+    ByAccount,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+struct Preferences {
+    /// Unset until the player picks a mode.
+    sort_mode: Option<SortMode>,
+}
+
+/// What this station has seen: how often each game was launched and when
+/// it first showed up in the station list.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlayStats {
+    pub launch_counts: HashMap<String, u32>,
+    /// Unix seconds.
+    pub first_seen: HashMap<String, u64>,
+}
+
+impl PlayStats {
+    pub fn record_launch(&mut self, product_id: &str) {
+        *self
+            .launch_counts
+            .entry(product_id.to_string())
+            .or_default() += 1;
+    }
+
+    /// Returns whether any product was new.
+    pub fn record_seen<'a>(
+        &mut self,
+        product_ids: impl IntoIterator<Item = &'a str>,
+        now: u64,
+    ) -> bool {
+        let mut changed = false;
+        for product_id in product_ids {
+            if !self.first_seen.contains_key(product_id) {
+                self.first_seen.insert(product_id.to_string(), now);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Stable, so ties keep the station order. That includes `RecentlyAdded`
+/// for games first seen in the same second, which on the first load is
+/// every game.
+pub fn sort_cards(cards: &mut [Card], mode: SortMode, stats: &PlayStats) {
+    match mode {
+        SortMode::Station => {}
+        SortMode::Alphabetical => {
+            cards.sort_by(|left, right| compare_titles(&left.title, &right.title))
+        }
+        SortMode::MostPlayed => cards.sort_by_key(|card| {
+            std::cmp::Reverse(
+                stats
+                    .launch_counts
+                    .get(&card.product_id)
+                    .copied()
+                    .unwrap_or(0),
+            )
+        }),
+        SortMode::RecentlyAdded => cards.sort_by_key(|card| {
+            std::cmp::Reverse(stats.first_seen.get(&card.product_id).copied().unwrap_or(0))
+        }),
+        SortMode::FreeFirst => cards.sort_by_key(|card| !card.is_free),
+        // Games that need no account come first, then one group per account.
+        SortMode::ByAccount => cards.sort_by(|left, right| {
+            compare_titles(&left.required_account, &right.required_account)
+                .then_with(|| compare_titles(&left.title, &right.title))
+        }),
+    }
+}
+
+/// Case-insensitive, Cyrillic before Latin, `ё` as `е` and digit runs by
+/// value, like `localeCompare(…, "ru", { numeric: true })` in the UI.
+pub fn compare_titles(left: &str, right: &str) -> Ordering {
+    let left = collation_key(left);
+    let right = collation_key(right);
+    left.cmp(&right)
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum CollationUnit {
+    Number(u128),
+    Letter(u8, char),
+}
+
+fn collation_key(value: &str) -> Vec<CollationUnit> {
+    let mut key = Vec::new();
+    let mut chars = value.trim().chars().flat_map(char::to_lowercase).peekable();
+    while let Some(ch) = chars.next() {
+        if let Some(digit) = ch.to_digit(10) {
+            let mut number = u128::from(digit);
+            while let Some(next) = chars.peek().and_then(|next| next.to_digit(10)) {
+                number = number.saturating_mul(10).saturating_add(u128::from(next));
+                chars.next();
+            }
+            key.push(CollationUnit::Number(number));
+            continue;
+        }
+        let ch = if ch == 'ё' { 'е' } else { ch };
+        let class = if !ch.is_alphabetic() {
+            0
+        } else if ('а'..='я').contains(&ch) {
+            1
+        } else if ch.is_ascii_alphabetic() {
+            2
+        } else {
+            3
+        };
+        key.push(CollationUnit::Letter(class, ch));
+    }
+    key
+}
+
+fn preferences_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(PREFERENCES_FILE_NAME))
+}
+
+fn play_stats_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(PLAY_STATS_FILE_NAME))
+}
+
+/// `default` comes from the owner config.
+pub fn load_sort_mode(default: SortMode) -> SortMode {
+    read_json::<Preferences>(preferences_path())
+        .and_then(|preferences| preferences.sort_mode)
+        .unwrap_or(default)
+}
+
+pub fn save_sort_mode(sort_mode: SortMode) -> Result<(), String> {
+    let mut preferences = read_json::<Preferences>(preferences_path()).unwrap_or_default();
+    preferences.sort_mode = Some(sort_mode);
+    write_json(preferences_path(), &preferences)
+}
+
+pub fn load_play_stats() -> PlayStats {
+    read_json(play_stats_path()).unwrap_or_default()
+}
+
+/// `lock` serializes the read and write with other updates of the file.
+pub fn record_launch(lock: &Mutex<()>, product_id: &str) {
+    let _guard = lock.lock();
+    let mut stats = load_play_stats();
+    stats.record_launch(product_id);
+    if let Err(err) = write_json(play_stats_path(), &stats) {
+        log::warn!("Failed to save play stats: {}", err);
+    }
+}
+
+pub fn record_seen<'a>(lock: &Mutex<()>, product_ids: impl IntoIterator<Item = &'a str>) {
+    let _guard = lock.lock();
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let mut stats = load_play_stats();
+    if stats.record_seen(product_ids, now) {
+        if let Err(err) = write_json(play_stats_path(), &stats) {
+            log::warn!("Failed to save play stats: {}", err);
+        }
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: Option<PathBuf>) -> Option<T> {
+    let contents = fs::read_to_string(path?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_json<T: Serialize>(path: Option<PathBuf>, value: &T) -> Result<(), String> {
+    let path =
+        path.ok_or_else(|| tr("Не удалось определить папку данных приложения").to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let contents = serde_json::to_string_pretty(value).map_err(|err| err.to_string())?;
+    fs::write(&path, contents).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(product_id: &str, title: &str, is_free: bool, account: &str) -> Card {
+        Card {
+            product_id: product_id.to_string(),
+            title: title.to_string(),
+            required_account: account.to_string(),
+            is_free,
+            ..Default::default()
+        }
+    }
+
+    fn cards() -> Vec<Card> {
+        vec![
+            card("rust", "Rust", false, "Steam"),
+            card("stalker", "Сталкер 2", false, "Steam"),
+            card("desktop", "Рабочий стол", true, ""),
+            card("days", "7 Days to Die", true, "Epic Games"),
+            card("dota", "Dota 2", true, "Steam"),
+        ]
+    }
+
+    fn ids(cards: &[Card]) -> Vec<&str> {
+        cards.iter().map(|card| card.product_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sort_alphabetical_cyrillic_first() {
+        let mut cards = cards();
+        sort_cards(&mut cards, SortMode::Alphabetical, &PlayStats::default());
+        assert_eq!(
+            ids(&cards),
+            vec!["days", "desktop", "stalker", "dota", "rust"]
+        );
+    }
+
+    #[test]
+    fn test_compare_titles_numeric_and_yo() {
+        assert_eq!(compare_titles("Game 2", "Game 10"), Ordering::Less);
+        assert_eq!(compare_titles("Ёлка", "елка"), Ordering::Equal);
+        assert_eq!(compare_titles("абв", "ABC"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_sort_most_played_and_recent() {
+        let mut stats = PlayStats::default();
+        stats.record_launch("dota");
+        stats.record_launch("dota");
+        stats.record_launch("rust");
+        let mut cards = cards();
+        sort_cards(&mut cards, SortMode::MostPlayed, &stats);
+        assert_eq!(
+            ids(&cards),
+            vec!["dota", "rust", "stalker", "desktop", "days"]
+        );
+
+        assert!(stats.record_seen(["rust", "stalker", "desktop", "days"], 100));
+        assert!(stats.record_seen(["dota", "rust"], 200));
+        assert!(!stats.record_seen(["dota"], 300));
+        let mut cards = self::cards();
+        sort_cards(&mut cards, SortMode::RecentlyAdded, &stats);
+        assert_eq!(
+            ids(&cards),
+            vec!["dota", "rust", "stalker", "desktop", "days"]
+        );
+    }
+
+    #[test]
+    fn test_sort_free_first_and_by_account() {
+        let mut cards = cards();
+        sort_cards(&mut cards, SortMode::FreeFirst, &PlayStats::default());
+        assert_eq!(
+            ids(&cards),
+            vec!["desktop", "days", "dota", "rust", "stalker"]
+        );
+
+        let mut cards = self::cards();
+        sort_cards(&mut cards, SortMode::ByAccount, &PlayStats::default());
+        assert_eq!(
+            ids(&cards),
+            vec!["desktop", "days", "stalker", "dota", "rust"]
+        );
+    }
+
+    #[test]
+    fn test_sort_mode_serde() {
+        let mode: SortMode = serde_json::from_str("\"mostPlayed\"").unwrap();
+        assert_eq!(mode, SortMode::MostPlayed);
+        assert!(serde_json::from_str::<SortMode>("\"random\"").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+// Only the Windows build reads the registry; the rest is exercised by tests.
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::config::data_dir;
+use crate::i18n::tr;
+use crate::{logging, StationInfo};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+pub const ESME_KEY: &str = "SOFTWARE\\ITKey\\Esme";
+const SERVERS_KEY: &str = "SOFTWARE\\ITKey\\Esme\\servers";
+const SELECTED_STATION_FILE_NAME: &str = "station.json";
+
+/// Read-only view of `HKLM`, so station discovery can be tested without a
+/// real Windows registry.
+pub trait RegistryReader {
+    fn read_string(&self, path: &str, name: &str) -> Result<String, String>;
+    fn subkeys(&self, path: &str) -> Result<Vec<String>, String>;
+}
+
+#[cfg(target_os = "windows")]
+pub struct WindowsRegistry;
+
+#[cfg(target_os = "windows")]
+impl RegistryReader for WindowsRegistry {
+    fn read_string(&self, path: &str, name: &str) -> Result<String, String> {
+        use winreg::enums::HKEY_LOCAL_MACHINE;
+        use winreg::RegKey;
+
+        let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(path)
+            .map_err(|err| err.to_string())?;
+        key.get_value(name).map_err(|err| err.to_string())
+    }
+
+    fn subkeys(&self, path: &str) -> Result<Vec<String>, String> {
+        use winreg::enums::HKEY_LOCAL_MACHINE;
+        use winreg::RegKey;
+
+        let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+            .open_subkey(path)
+            .map_err(|err| err.to_string())?;
+        key.enum_keys()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| err.to_string())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfiguredStation {
+    pub info: StationInfo,
+    pub is_last_server: bool,
+}
+
+/// Every station under `Esme\servers` that has an `auth_token`, with the
+/// Esme client's `last_server` first.
+pub fn list_registry_stations(
+    registry: &dyn RegistryReader,
+) -> Result<Vec<ConfiguredStation>, String> {
+    let last_server = registry.read_string(ESME_KEY, "last_server").ok();
+    let mut stations = Vec::new();
+    for uuid in registry.subkeys(SERVERS_KEY)? {
+        match read_station_token(registry, &uuid) {
+            Ok(token) => stations.push(ConfiguredStation {
+                is_last_server: last_server.as_deref() == Some(uuid.as_str()),
+                info: StationInfo { uuid, token },
+            }),
+            Err(err) => {
+                logging::hide_station_uuid(&uuid);
+                log::warn!("Skipping station {}: {}", uuid, err);
+            }
+        }
+    }
+    stations.sort_by(|left, right| {
+        right
+            .is_last_server
+            .cmp(&left.is_last_server)
+            .then_with(|| left.info.uuid.cmp(&right.info.uuid))
+    });
+    Ok(stations)
+}
+
+/// Uses the station the player picked while it is still configured,
+/// otherwise whatever the Esme client used last.
+pub fn registry_station(
+    registry: &dyn RegistryReader,
+    preferred: Option<&str>,
+) -> Result<StationInfo, String> {
+    if let Some(uuid) = preferred {
+        match read_station_token(registry, uuid) {
+            Ok(token) => {
+                return Ok(StationInfo {
+                    uuid: uuid.to_string(),
+                    token,
+                })
+            }
+            Err(err) => {
+                logging::hide_station_uuid(uuid);
+                log::warn!("Selected station {} unavailable: {}", uuid, err);
+            }
+        }
+    }
+
+    let uuid = registry.read_string(ESME_KEY, "last_server")?;
+    let token = read_station_token(registry, &uuid)?;
+    Ok(StationInfo { uuid, token })
+}
+
+fn read_station_token(registry: &dyn RegistryReader, uuid: &str) -> Result<String, String> {
+    registry.read_string(&format!("{}\\{}", SERVERS_KEY, uuid), "auth_token")
+}
+
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct SelectedStation {
+    station_uuid: Option<String>,
+}
+
+fn selected_station_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(SELECTED_STATION_FILE_NAME))
+}
+
+pub fn load_selected_station() -> Option<String> {
+    let contents = fs::read_to_string(selected_station_path()?).ok()?;
+    let selected: SelectedStation = serde_json::from_str(&contents).ok()?;
+    selected.station_uuid.filter(|uuid| !uuid.is_empty())
+}
+
+pub fn save_selected_station(uuid: &str) -> Result<(), String> {
+    let path = selected_station_path()
+        .ok_or_else(|| tr("Не удалось определить папку данных приложения").to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let payload = SelectedStation {
+        station_uuid: Some(uuid.to_string()),
+    };
+    let contents = serde_json::to_string_pretty(&payload).map_err(|err| err.to_string())?;
+    fs::write(&path, contents).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct FakeRegistry {
+        values: HashMap<(String, String), String>,
+        subkeys: HashMap<String, Vec<String>>,
+    }
+
+    impl FakeRegistry {
+        fn with_station(mut self, uuid: &str, token: Option<&str>) -> Self {
+            self.subkeys
+                .entry(SERVERS_KEY.to_string())
+                .or_default()
+                .push(uuid.to_string());
+            if let Some(token) = token {
+                self.values.insert(
+                    (
+                        format!("{}\\{}", SERVERS_KEY, uuid),
+                        "auth_token".to_string(),
+                    ),
+                    token.to_string(),
+                );
+            }
+            self
+        }
+
+        fn with_last_server(mut self, uuid: &str) -> Self {
+            self.values.insert(
+                (ESME_KEY.to_string(), "last_server".to_string()),
+                uuid.to_string(),
+            );
+            self
+        }
+    }
+
+    impl RegistryReader for FakeRegistry {
+        fn read_string(&self, path: &str, name: &str) -> Result<String, String> {
+            self.values
+                .get(&(path.to_string(), name.to_string()))
+                .cloned()
+                .ok_or_else(|| format!("{}\\{} not found", path, name))
+        }
+
+        fn subkeys(&self, path: &str) -> Result<Vec<String>, String> {
+            self.subkeys
+                .get(path)
+                .cloned()
+                .ok_or_else(|| format!("{} not found", path))
+        }
+    }
+
+    #[test]
+    fn test_list_registry_stations_last_server_first() {
+        let registry = FakeRegistry::default()
+            .with_station("uuid-a", Some("token-a"))
+            .with_station("uuid-b", Some("token-b"))
+            .with_station("uuid-c", None)
+            .with_last_server("uuid-b");
+        let stations = list_registry_stations(&registry).unwrap();
+        let uuids: Vec<&str> = stations.iter().map(|s| s.info.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["uuid-b", "uuid-a"]);
+        assert!(stations[0].is_last_server);
+        assert_eq!(stations[1].info.token, "token-a");
+    }
+
+    #[test]
+    fn test_list_registry_stations_without_servers_key() {
+        assert!(list_registry_stations(&FakeRegistry::default()).is_err());
+    }
+
+    #[test]
+    fn test_registry_station_prefers_selection() {
+        let registry = FakeRegistry::default()
+            .with_station("uuid-a", Some("token-a"))
+            .with_station("uuid-b", Some("token-b"))
+            .with_last_server("uuid-b");
+
+        let selected = registry_station(&registry, Some("uuid-a")).unwrap();
+        assert_eq!(selected.uuid, "uuid-a");
+        assert_eq!(selected.token, "token-a");
+
+        let fallback = registry_station(&registry, Some("uuid-gone")).unwrap();
+        assert_eq!(fallback.uuid, "uuid-b");
+
+        let last = registry_station(&registry, None).unwrap();
+        assert_eq!(last.token, "token-b");
+    }
+}
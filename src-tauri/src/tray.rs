@@ -0,0 +1,165 @@
+use crate::i18n::tr;
+use crate::{kiosk, launch_product, SharedState};
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager};
+
+const TRAY_ID: &str = "launcher";
+const MENU_OPEN: &str = "open";
+const MENU_EXIT: &str = "exit";
+const MENU_RECENT_PREFIX: &str = "recent:";
+
+/// The tray icon is created hidden at startup and only shown while the
+/// launcher window is hidden in Desktop mode.
+pub fn setup_tray(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_tray_menu(app, &[])?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Drova Launcher")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                if let Err(err) = restore_launcher(tray.app_handle()) {
+                    log::warn!("Failed to restore launcher: {}", err);
+                }
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    let tray = builder.build(app)?;
+    tray.set_visible(false)?;
+    Ok(())
+}
+
+/// Hides the launcher window and shows the tray icon. Falls back to
+/// minimizing when the tray could not be created.
+pub fn hide_to_tray(app: &AppHandle) -> Result<(), String> {
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    match app.tray_by_id(TRAY_ID) {
+        Some(tray) => {
+            refresh_tray_menu(app);
+            tray.set_visible(true).map_err(|err| err.to_string())?;
+            window.hide().map_err(|err| err.to_string())
+        }
+        None => window.minimize().map_err(|err| err.to_string()),
+    }
+}
+
+pub fn restore_launcher(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("main") {
+        window.show().map_err(|err| err.to_string())?;
+        window.unminimize().map_err(|err| err.to_string())?;
+        window.set_focus().map_err(|err| err.to_string())?;
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_visible(false).map_err(|err| err.to_string())?;
+    }
+    let _ = app.emit("launcher_restored", ());
+    Ok(())
+}
+
+/// Rebuilds the menu so the quick-launch list matches the recent launches.
+pub fn refresh_tray_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let recent = recent_items(app);
+    match build_tray_menu(app, &recent) {
+        Ok(menu) => {
+            if let Err(err) = tray.set_menu(Some(menu)) {
+                log::warn!("Failed to update tray menu: {}", err);
+            }
+        }
+        Err(err) => log::warn!("Failed to build tray menu: {}", err),
+    }
+}
+
+fn recent_items(app: &AppHandle) -> Vec<(String, String)> {
+    let state = app.state::<SharedState>();
+    let recent = match state.recent_launches.lock() {
+        Ok(recent) => recent.clone(),
+        Err(_) => return Vec::new(),
+    };
+    let Ok(catalog) = state.catalog.lock() else {
+        return Vec::new();
+    };
+    recent
+        .into_iter()
+        .map(|product_id| {
+            let title = catalog
+                .cards
+                .iter()
+                .find(|card| card.product_id == product_id)
+                .map(|card| card.title.clone())
+                .unwrap_or_else(|| product_id.clone());
+            (product_id, title)
+        })
+        .collect()
+}
+
+fn build_tray_menu(
+    app: &AppHandle,
+    recent: &[(String, String)],
+) -> tauri::Result<Menu<tauri::Wry>> {
+    let open = MenuItem::with_id(app, MENU_OPEN, tr("Открыть лаунчер"), true, None::<&str>)?;
+    let recent_items = recent
+        .iter()
+        .map(|(product_id, title)| {
+            MenuItem::with_id(
+                app,
+                format!("{}{}", MENU_RECENT_PREFIX, product_id),
+                title,
+                true,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let recent_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> = recent_items
+        .iter()
+        .map(|item| item as &dyn tauri::menu::IsMenuItem<tauri::Wry>)
+        .collect();
+    let recent_menu =
+        Submenu::with_items(app, tr("Недавние игры"), !recent.is_empty(), &recent_refs)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let exit = MenuItem::with_id(app, MENU_EXIT, tr("Выход"), true, None::<&str>)?;
+    Menu::with_items(app, &[&open, &recent_menu, &separator, &exit])
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        MENU_OPEN => {
+            if let Err(err) = restore_launcher(app) {
+                log::warn!("Failed to restore launcher: {}", err);
+            }
+        }
+        MENU_EXIT if kiosk::is_enabled(app) => kiosk::request_exit(app),
+        MENU_EXIT => app.exit(0),
+        _ => {
+            let Some(product_id) = id.strip_prefix(MENU_RECENT_PREFIX) else {
+                return;
+            };
+            quick_launch(app.clone(), product_id.to_string());
+        }
+    }
+}
+
+/// Hooks may take a while, so quick launches run off the event loop. On
+/// failure the launcher is brought back so the player is not left without UI.
+fn quick_launch(app: AppHandle, product_id: String) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<SharedState>();
+        if let Err(err) = launch_product(&app, &state, &product_id, None) {
+            log::warn!("Quick launch of {} failed: {}", product_id, err);
+            let _ = restore_launcher(&app);
+        }
+    });
+}
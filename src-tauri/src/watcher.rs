@@ -0,0 +1,231 @@
+use crate::config::{config_path, load_dotenv};
+use crate::{current_config, get_station_info, reload_config, tray, SharedState, StationInfo};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Editors and the Esme client write in several steps; changes that arrive
+/// within this window are handled once.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StationChangedPayload {
+    uuid: String,
+}
+
+/// Watches every place station credentials come from and emits
+/// `station_changed` when they no longer match the station the cards were
+/// loaded for. The UI reloads the cards in response. The managed config is
+/// reloaded on every change, since `launcher.json` is one of those places.
+pub fn watch_station_changes(app: AppHandle) {
+    let (tx, rx) = mpsc::channel::<()>();
+
+    let file_watcher = match watch_credential_files(tx.clone()) {
+        Ok(watcher) => Some(watcher),
+        Err(err) => {
+            log::warn!("Credential file watch unavailable: {}", err);
+            None
+        }
+    };
+
+    #[cfg(target_os = "windows")]
+    registry::watch_esme_key(tx);
+    #[cfg(not(target_os = "windows"))]
+    drop(tx);
+
+    std::thread::spawn(move || {
+        // Dropping the watcher stops file notifications.
+        let _file_watcher = file_watcher;
+        while rx.recv().is_ok() {
+            wait_until_quiet(&rx, DEBOUNCE);
+            check_station(&app);
+        }
+    });
+}
+
+fn check_station(app: &AppHandle) {
+    let state = app.state::<SharedState>();
+    load_dotenv();
+    // An invalid file is logged and reported by the next command.
+    if reload_config(&state).is_ok() {
+        // Menu items follow a changed `locale`.
+        tray::refresh_tray_menu(app);
+    }
+    let fresh = match get_station_info(&current_config(&state)) {
+        Ok(station) => station,
+        Err(err) => {
+            log::warn!("Station credentials unavailable after change: {}", err);
+            return;
+        }
+    };
+    {
+        let Ok(mut current) = state.current_station.lock() else {
+            return;
+        };
+        if !replace_if_changed(&mut current, &fresh) {
+            return;
+        }
+    }
+    log::info!("Station credentials changed: uuid={}", fresh.uuid);
+    let _ = app.emit(
+        "station_changed",
+        StationChangedPayload { uuid: fresh.uuid },
+    );
+}
+
+/// Swallows notifications until none arrived for `window`.
+fn wait_until_quiet(rx: &mpsc::Receiver<()>, window: Duration) {
+    while rx.recv_timeout(window).is_ok() {}
+}
+
+/// Whether `fresh` differs from the station the cards were loaded for; if
+/// so it becomes the current one, so the same change is reported once.
+/// Before the first load there is nothing to compare: that load picks up
+/// the change itself.
+fn replace_if_changed(current: &mut Option<StationInfo>, fresh: &StationInfo) -> bool {
+    match current.as_ref() {
+        None => false,
+        Some(station) if station == fresh => false,
+        Some(_) => {
+            *current = Some(fresh.clone());
+            true
+        }
+    }
+}
+
+/// `.env` and `launcher.json` may not exist yet, so their directories are
+/// watched instead of the files.
+fn watch_credential_files(tx: mpsc::Sender<()>) -> notify::Result<notify::RecommendedWatcher> {
+    let targets = credential_files();
+    let dirs: Vec<PathBuf> = targets
+        .iter()
+        .filter_map(|path| path.parent())
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+        .collect();
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result {
+            if event.paths.iter().any(|path| targets.contains(path)) {
+                let _ = tx.send(());
+            }
+        }
+    })?;
+    for dir in dirs {
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    }
+    Ok(watcher)
+}
+
+fn credential_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(path) = config_path() {
+        files.push(path);
+    }
+    if let Ok(dir) = std::env::current_dir() {
+        files.push(dir.join(".env"));
+    }
+    files
+}
+
+#[cfg(target_os = "windows")]
+mod registry {
+    use crate::station::ESME_KEY;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegNotifyChangeKeyValue, KEY_NOTIFY, REG_NOTIFY_CHANGE_LAST_SET, REG_NOTIFY_CHANGE_NAME,
+    };
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+    /// Blocks on `RegNotifyChangeKeyValue` for the Esme key and its
+    /// `servers` subkeys, which cover both `last_server` and tokens.
+    pub fn watch_esme_key(tx: mpsc::Sender<()>) {
+        std::thread::spawn(move || loop {
+            let key = match RegKey::predef(HKEY_LOCAL_MACHINE)
+                .open_subkey_with_flags(ESME_KEY, KEY_NOTIFY)
+            {
+                Ok(key) => key,
+                Err(err) => {
+                    log::warn!("Cannot watch Esme registry key: {}", err);
+                    std::thread::sleep(RETRY_DELAY);
+                    continue;
+                }
+            };
+            // SAFETY: `key` owns the handle and outlives this call, which is
+            // synchronous (no event, `fAsynchronous` is 0): it blocks until
+            // a change and does not keep the handle afterwards.
+            let status = unsafe {
+                RegNotifyChangeKeyValue(
+                    key.raw_handle(),
+                    1,
+                    REG_NOTIFY_CHANGE_NAME | REG_NOTIFY_CHANGE_LAST_SET,
+                    0,
+                    0,
+                )
+            };
+            if status != ERROR_SUCCESS {
+                log::warn!("RegNotifyChangeKeyValue failed: {}", status);
+                std::thread::sleep(RETRY_DELAY);
+                continue;
+            }
+            if tx.send(()).is_err() {
+                return;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn station(uuid: &str, token: &str) -> StationInfo {
+        StationInfo {
+            uuid: uuid.to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replace_if_changed() {
+        let mut current = None;
+        assert!(!replace_if_changed(&mut current, &station("a", "t1")));
+        assert_eq!(current, None);
+
+        current = Some(station("a", "t1"));
+        assert!(!replace_if_changed(&mut current, &station("a", "t1")));
+        assert!(replace_if_changed(&mut current, &station("a", "t2")));
+        assert_eq!(current, Some(station("a", "t2")));
+        // Reported once.
+        assert!(!replace_if_changed(&mut current, &station("a", "t2")));
+        assert!(replace_if_changed(&mut current, &station("b", "t2")));
+    }
+
+    #[test]
+    fn test_wait_until_quiet_drains_a_burst() {
+        let (tx, rx) = mpsc::channel();
+        let sender = std::thread::spawn(move || {
+            for _ in 0..5 {
+                tx.send(()).unwrap();
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            tx
+        });
+        rx.recv().unwrap();
+        let started = Instant::now();
+        wait_until_quiet(&rx, Duration::from_millis(100));
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        let _tx = sender.join().unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+}
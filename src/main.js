@@ -1,7 +1,11 @@
 import {
   applyCardFilters,
+  applyCardsDiff,
   buildCardFilterOptions,
-  LICENSE_FILTERS
+  formatStatusProgress,
+  FILTER_ANY,
+  LICENSE_FILTERS,
+  STATUS_PHASES
 } from "./model.js";
 
 const grid = document.getElementById("grid");
@@ -9,7 +13,11 @@ const statusEl = document.getElementById("status");
 const statusText = document.getElementById("statusText");
 const statusSub = document.getElementById("statusSub");
 const retryBtn = document.getElementById("retryBtn");
+const diagnosticsBtn = document.getElementById("diagnosticsBtn");
 const statusClose = document.getElementById("statusClose");
+const exitForm = document.getElementById("exitForm");
+const exitPin = document.getElementById("exitPin");
+const exitError = document.getElementById("exitError");
 
 document.addEventListener("contextmenu", (event) => {
   event.preventDefault();
@@ -34,15 +42,24 @@ let listen = null;
 let started = false;
 let lastLoadPromise = null;
 let loadingActive = false;
+// A `station_changed` that arrived while cards were loading: the load may
+// have used the old credentials, so it runs again once it finishes.
+let stationChangePending = false;
 let progressLabel = "";
 let serverName = "";
 let serverDescription = "";
 let serverHardware = null;
+let stations = [];
 let activeLaunchCard = null;
 let allCards = [];
+let cardsRenderPending = false;
 let activeFilterDropdown = null;
 let gameFilterQuery = "";
+let gameSearch = { query: "", titles: null };
 let filters = createInitialFilters();
+let sortMode = "station";
+let productDetails = null;
+let parentalStatus = null;
 const skipAutoInit = window.__TAURI_TEST_DISABLE_AUTO_INIT === true;
 
 const licenseFilterItems = Object.freeze([
@@ -50,6 +67,15 @@ const licenseFilterItems = Object.freeze([
   { value: LICENSE_FILTERS.PAID, label: "Платная" }
 ]);
 
+// "station" is the backend default and shows as the "all" item.
+const sortModeItems = Object.freeze([
+  { value: "alphabetical", label: "По алфавиту" },
+  { value: "mostPlayed", label: "Популярные на станции" },
+  { value: "recentlyAdded", label: "Недавно добавленные" },
+  { value: "freeFirst", label: "Сначала бесплатные" },
+  { value: "byAccount", label: "По игровому аккаунту" }
+]);
+
 function resolveTauriApi() {
   const tauri = window.__TAURI__ || null;
   return {
@@ -83,7 +109,9 @@ function createInitialFilters() {
   return {
     games: [],
     license: LICENSE_FILTERS.ANY,
-    account: LICENSE_FILTERS.ANY
+    account: FILTER_ANY,
+    publisher: FILTER_ANY,
+    ageGroup: FILTER_ANY
   };
 }
 
@@ -91,6 +119,7 @@ function resetFilters() {
   filters = createInitialFilters();
   activeFilterDropdown = null;
   gameFilterQuery = "";
+  gameSearch = { query: "", titles: null };
 }
 
 function setCards(cards = [], options = {}) {
@@ -109,10 +138,21 @@ retryBtn.addEventListener("click", () => {
   }
 });
 
+diagnosticsBtn.addEventListener("click", () => exportDiagnostics());
+
 if (statusClose) {
   statusClose.addEventListener("click", () => clearStatus());
 }
 
+// Kiosk mode: closing the window only opens this prompt.
+if (exitForm) {
+  exitForm.addEventListener("submit", (event) => {
+    event.preventDefault();
+    exitLauncher(exitPin.value);
+  });
+  document.getElementById("exitClose")?.addEventListener("click", () => closeModal("exitModal"));
+}
+
 statusEl.addEventListener("click", (event) => {
   if (event.target === statusEl) {
     clearStatus();
@@ -207,12 +247,68 @@ function renderHardware(hardware) {
   `;
 }
 
+function renderStationOptions() {
+  return stations.map(station => {
+    const label = String(station.name || "").trim() || station.uuid;
+    const selectedClass = station.isSelected ? " is-selected" : "";
+    return `<button class="page-action station-option${selectedClass}" type="button" data-station-uuid="${escapeHtml(station.uuid)}">${escapeHtml(label)}</button>`;
+  }).join("");
+}
+
+function renderStationPicker() {
+  if (stations.length < 2) return "";
+  return `
+    <div class="modal-overlay is-hidden" id="stationsModal" data-modal>
+      <div class="modal">
+        <div class="modal__header">
+          <div class="modal__title">Выбор станции</div>
+          <button class="modal__close" type="button" data-close="stationsModal">×</button>
+        </div>
+        <div class="modal__body station-options">${renderStationOptions()}</div>
+      </div>
+    </div>
+  `;
+}
+
+function renderParentalButton() {
+  if (!parentalStatus?.configured) return "";
+  const label = parentalStatus.active
+    ? `Родительский контроль: ${escapeHtml(parentalStatus.maxAge)}+`
+    : "Включить родительский контроль";
+  return `<button class="page-action" id="toggleParental" type="button">${label}</button>`;
+}
+
+function renderParentalDialog() {
+  if (!parentalStatus?.canUnlock) return "";
+  return `
+    <div class="modal-overlay is-hidden" id="parentalModal" data-modal>
+      <div class="modal">
+        <div class="modal__header">
+          <div class="modal__title">Отключить родительский контроль</div>
+          <button class="modal__close" type="button" data-close="parentalModal">×</button>
+        </div>
+        <form class="modal__body parental-form" id="parentalForm">
+          <input class="parental-form__pin" id="parentalPin" type="password" inputmode="numeric" autocomplete="off" placeholder="PIN владельца">
+          <button class="page-action" type="submit">Отключить</button>
+        </form>
+      </div>
+    </div>
+  `;
+}
+
 function renderServerDialogs() {
+  const stationButton = stations.length > 1
+    ? "<button class=\"page-action\" id=\"openStations\" type=\"button\">Сменить станцию</button>"
+    : "";
   return `
     <div class="page-actions">
       <button class="page-action" id="openDescription" type="button">Описание сервера и контакты</button>
       <button class="page-action" id="openHardware" type="button">Технические характеристики</button>
+      ${stationButton}
+      ${renderParentalButton()}
     </div>
+    ${renderStationPicker()}
+    ${renderParentalDialog()}
     <div class="modal-overlay is-hidden" id="descriptionModal" data-modal>
       <div class="modal">
         <div class="modal__header">
@@ -252,6 +348,65 @@ function setStationDetails(details = {}) {
   }
 }
 
+function renderProductDetails(details) {
+  if (!details) {
+    return "<div class=\"server-details__empty\">Загружаем описание…</div>";
+  }
+  const picture = details.pagePicture
+    ? `<img class="product-details__picture" src="${escapeHtml(details.pagePicture)}" alt="">`
+    : "";
+  const license = details.isFree ? "Бесплатная" : (details.licenseType || "Платная");
+  const facts = [
+    ["Издатель", details.publisher],
+    ["Разработчик", details.developer],
+    ["Возраст", details.ageGroup],
+    ["Учетная запись", details.requiredAccount],
+    ["Лицензия", license]
+  ]
+    .filter(([, value]) => String(value || "").trim())
+    .map(([label, value]) => `<div class="product-details__fact"><span>${label}</span>${escapeHtml(value)}</div>`)
+    .join("");
+  const launch = details.canLaunch
+    ? "<div class=\"product-details__launch\">Доступна для запуска</div>"
+    : `<div class="product-details__launch is-unavailable">Запуск недоступен${details.launchError ? `: ${escapeHtml(details.launchError)}` : ""}</div>`;
+  const descriptionRu = String(details.descriptionRu || "").trim();
+  const descriptionEn = String(details.descriptionEn || "").trim();
+  const descriptions = [descriptionRu, descriptionEn]
+    .filter(Boolean)
+    .map(text => `<div class="product-details__description">${escapeHtml(text)}</div>`)
+    .join("") || "<div class=\"server-details__empty\">Описание не указано.</div>";
+
+  return `
+    ${picture}
+    <div class="product-details__facts">${facts}</div>
+    ${launch}
+    ${descriptions}
+  `;
+}
+
+function setProductDetails(details) {
+  productDetails = details;
+  const titleEl = document.getElementById("productModalTitle");
+  if (titleEl) {
+    titleEl.textContent = details?.title || "Об игре";
+  }
+  const bodyEl = document.getElementById("productDetails");
+  if (bodyEl) {
+    bodyEl.innerHTML = renderProductDetails(productDetails);
+  }
+}
+
+// Without the backend only the card fields are known.
+function buildCardDetails(card) {
+  return {
+    ...card,
+    descriptionRu: card.alt || "",
+    descriptionEn: "",
+    canLaunch: false,
+    launchError: "запуск доступен только в приложении"
+  };
+}
+
 function renderCard(card) {
   const title = escapeHtml(card.title || "");
   const alt = escapeHtml(card.alt || "");
@@ -267,6 +422,10 @@ function renderCard(card) {
   const placeholderClass = rawImageUrl ? "" : "card--placeholder";
   const loadingClass = card.isLoading ? "is-loading" : "";
   const loadingAttr = card.isLoading ? " data-loading=\"1\"" : "";
+  const pinned = card.pinned ? "<div class=\"gameList__item-badge-pinned\">Рекомендуем</div>" : "";
+  const favoriteLabel = card.favorite ? "Убрать из избранного" : "В избранное";
+  const detailsButton = card.isLoading ? "" : `<button class=\"card-details\" type=\"button\" data-details-open=\"${escapeHtml(card.productId)}\" aria-label=\"Об игре\" title=\"Об игре\">i</button>`;
+  const favorite = card.isLoading || !invoke ? "" : `<button class=\"card-favorite${card.favorite ? " is-active" : ""}\" type=\"button\" data-favorite-toggle=\"${escapeHtml(card.productId)}\" aria-label=\"${favoriteLabel}\" title=\"${favoriteLabel}\">${card.favorite ? "★" : "☆"}</button>`;
 
   return `
     <div class=\"gameList__item gameList__item-thumb ivu-card ivu-card-bordered ${placeholderClass} ${loadingClass}\" data-product-id=\"${escapeHtml(card.productId)}\" data-image-url=\"${escapeHtml(rawImageUrl)}\" data-is-desktop=\"${isDesktop ? "1" : "0"}\"${loadingAttr}>
@@ -275,7 +434,9 @@ function renderCard(card) {
         <div class=\"gameList__item-image\"${imageStyle}></div>
         <div class=\"gameList__item-title\"><span>${title}</span></div>
         <div class=\"gameList__item-start gameList__item-start_active\">${startContent}</div>
-        <div class=\"gameList__item-badges\">${required}${free}</div>
+        <div class=\"gameList__item-badges\">${pinned}${required}${free}</div>
+        ${detailsButton}
+        ${favorite}
       </div>
     </div>
   `;
@@ -288,9 +449,27 @@ function shouldShowFilters(cards = []) {
 function getVisibleGameFilterOptions(options = []) {
   const query = String(gameFilterQuery || "").trim().toLocaleLowerCase("ru");
   if (!query) return options;
+  if (gameSearch.titles && gameSearch.query === gameFilterQuery) {
+    return gameSearch.titles.filter(title => options.includes(title));
+  }
   return options.filter(option => option.toLocaleLowerCase("ru").includes(query));
 }
 
+// The backend search also matches transliteration, the wrong keyboard
+// layout and typos; substring matching stays as the offline fallback.
+async function searchGames(query) {
+  if (!invoke || !query.trim()) return;
+  try {
+    const cards = await invoke("search_cards", { query });
+    if (query !== gameFilterQuery) return;
+    const titles = [...new Set((cards || []).map(card => String(card?.title || "").trim()).filter(Boolean))];
+    gameSearch = { query, titles };
+    render(allCards);
+  } catch (error) {
+    // Keep the substring results.
+  }
+}
+
 function renderSelectedGameTags() {
   return filters.games.map(game => `
     <span class="filter-tag">
@@ -344,16 +523,16 @@ function renderGameFilter(options = []) {
 function renderSingleFilter({ key, placeholder, selectedValue, allLabel, options = [] }) {
   const isOpen = activeFilterDropdown === key;
   const items = [
-    { value: LICENSE_FILTERS.ANY, label: allLabel },
+    { value: FILTER_ANY, label: allLabel },
     ...options
   ].map(option => {
     const selected = option.value === selectedValue;
     return `<li class="ivu-select-item${selected ? " ivu-select-item-selected" : ""}" data-filter-option="${escapeHtml(key)}" data-value="${escapeHtml(option.value)}">${escapeHtml(option.label)}</li>`;
   }).join("");
   const selectedLabel = options
-    .concat({ value: LICENSE_FILTERS.ANY, label: allLabel })
+    .concat({ value: FILTER_ANY, label: allLabel })
     .find(option => option.value === selectedValue)?.label;
-  const showPlaceholder = !selectedLabel || selectedValue === LICENSE_FILTERS.ANY;
+  const showPlaceholder = !selectedLabel || selectedValue === FILTER_ANY;
 
   return `
     <div class="filter filter_${escapeHtml(key)} ivu-col ivu-col-span-6">
@@ -384,6 +563,8 @@ function renderFilters(cards = []) {
 
   const options = buildCardFilterOptions(cards);
   const accountOptions = options.accounts.map(account => ({ value: account, label: account }));
+  const publisherOptions = options.publishers.map(publisher => ({ value: publisher, label: publisher }));
+  const ageGroupOptions = options.ageGroups.map(ageGroup => ({ value: ageGroup, label: ageGroup }));
 
   return `
     <div class="stations__filter ivu-row ivu-row-flex filters-bar" id="filtersBar">
@@ -402,6 +583,27 @@ function renderFilters(cards = []) {
         allLabel: "Любой игровой аккаунт",
         options: accountOptions
       })}
+      ${publisherOptions.length > 0 ? renderSingleFilter({
+        key: "publisher",
+        placeholder: "Издатель",
+        selectedValue: filters.publisher,
+        allLabel: "Любой издатель",
+        options: publisherOptions
+      }) : ""}
+      ${ageGroupOptions.length > 0 ? renderSingleFilter({
+        key: "ageGroup",
+        placeholder: "Возраст",
+        selectedValue: filters.ageGroup,
+        allLabel: "Любой возраст",
+        options: ageGroupOptions
+      }) : ""}
+      ${invoke ? renderSingleFilter({
+        key: "sort",
+        placeholder: "Сортировка",
+        selectedValue: sortMode === "station" ? FILTER_ANY : sortMode,
+        allLabel: "Порядок станции",
+        options: sortModeItems
+      }) : ""}
     </div>
   `;
 }
@@ -423,6 +625,15 @@ function render(cards) {
       ${renderServerDialogs()}
     </div>
     ${renderFilters(cards)}
+    <div class="modal-overlay is-hidden" id="productModal" data-modal>
+      <div class="modal">
+        <div class="modal__header">
+          <div class="modal__title" id="productModalTitle">${escapeHtml(productDetails?.title || "Об игре")}</div>
+          <button class="modal__close" type="button" data-close="productModal">×</button>
+        </div>
+        <div class="modal__body" id="productDetails">${renderProductDetails(productDetails)}</div>
+      </div>
+    </div>
     <div class="launch-overlay is-hidden" id="launchOverlay">
       <div class="launch-overlay__text">Нажмите в любом месте для возврата к списку игр</div>
     </div>
@@ -447,6 +658,31 @@ function render(cards) {
   if (openHardware) {
     openHardware.addEventListener("click", () => openModal("hardwareModal"));
   }
+  const openStations = document.getElementById("openStations");
+  if (openStations) {
+    openStations.addEventListener("click", () => openModal("stationsModal"));
+  }
+  const toggleParental = document.getElementById("toggleParental");
+  if (toggleParental) {
+    toggleParental.addEventListener("click", () => {
+      if (!parentalStatus?.active) {
+        lockParentalMode();
+      } else if (parentalStatus.canUnlock) {
+        openModal("parentalModal");
+        document.getElementById("parentalPin")?.focus();
+      }
+    });
+  }
+  const parentalForm = document.getElementById("parentalForm");
+  if (parentalForm) {
+    parentalForm.addEventListener("submit", (event) => {
+      event.preventDefault();
+      unlockParentalMode(document.getElementById("parentalPin")?.value || "");
+    });
+  }
+  grid.querySelectorAll("[data-station-uuid]").forEach(btn => {
+    btn.addEventListener("click", () => selectStation(btn.dataset.stationUuid));
+  });
 
   const modals = grid.querySelectorAll("[data-modal]");
   modals.forEach(modal => {
@@ -467,6 +703,22 @@ function render(cards) {
     launchOverlay.addEventListener("click", () => hideLaunchOverlay(false));
   }
 
+  grid.querySelectorAll("[data-favorite-toggle]").forEach(btn => {
+    btn.addEventListener("click", (event) => {
+      event.preventDefault();
+      event.stopPropagation();
+      toggleFavorite(btn.dataset.favoriteToggle);
+    });
+  });
+
+  grid.querySelectorAll("[data-details-open]").forEach(btn => {
+    btn.addEventListener("click", (event) => {
+      event.preventDefault();
+      event.stopPropagation();
+      openProductDetails(btn.dataset.detailsOpen);
+    });
+  });
+
   [...grid.querySelectorAll(".gameList__item")].forEach(cardEl => {
     const imageUrl = cardEl.dataset.imageUrl;
     if (imageUrl) {
@@ -489,7 +741,7 @@ function render(cards) {
       }
       showLaunchOverlay(5000);
       try {
-        await withTimeout(invoke("launch_game", { productId }), 10_000, "Таймаут запуска");
+        await withTimeout(invoke("launch_game", { productId }), 60_000, "Таймаут запуска");
       } catch (error) {
         cardEl.classList.remove("is-launching");
         activeLaunchCard = null;
@@ -531,7 +783,7 @@ function handleFiltersClick(event) {
     event.preventDefault();
     event.stopPropagation();
     const key = option.dataset.filterOption;
-    const value = option.dataset.value || LICENSE_FILTERS.ANY;
+    const value = option.dataset.value || FILTER_ANY;
 
     if (key === "game") {
       filters = {
@@ -553,6 +805,20 @@ function handleFiltersClick(event) {
       filters = { ...filters, account: value };
     }
 
+    if (key === "publisher") {
+      filters = { ...filters, publisher: value };
+    }
+
+    if (key === "ageGroup") {
+      filters = { ...filters, ageGroup: value };
+    }
+
+    if (key === "sort") {
+      activeFilterDropdown = null;
+      setSortMode(value === FILTER_ANY ? "station" : value);
+      return;
+    }
+
     activeFilterDropdown = null;
     gameFilterQuery = "";
     render(allCards);
@@ -589,6 +855,7 @@ function handleFiltersInput(event) {
   gameFilterQuery = input.value || "";
   activeFilterDropdown = "game";
   render(allCards);
+  searchGames(gameFilterQuery);
 }
 
 function handleFiltersKeydown(event) {
@@ -640,6 +907,124 @@ function hideLaunchOverlay(force = false) {
     activeLaunchCard.classList.remove("is-launching");
     activeLaunchCard = null;
   }
+  renderPendingCards();
+}
+
+// Re-rendering the grid replaces the launch overlay and the modals inside
+// it, so `cards_changed` waits while any of them is open.
+function isGridBusy() {
+  return Boolean(activeLaunchCard) || Boolean(grid.querySelector(".modal-overlay:not(.is-hidden)"));
+}
+
+function renderPendingCards() {
+  if (!cardsRenderPending || isGridBusy()) return;
+  cardsRenderPending = false;
+  render(allCards);
+}
+
+async function loadSortMode() {
+  if (!invoke) return;
+  try {
+    sortMode = (await invoke("get_sort_mode")) || "station";
+  } catch (error) {
+    // The station order is fine if the preference can't be read.
+  }
+}
+
+async function setSortMode(mode) {
+  if (!invoke) return;
+  try {
+    const cards = await invoke("set_sort_mode", { mode });
+    sortMode = mode;
+    setCards(cards || [], { resetFilters: false });
+  } catch (error) {
+    setStatus("Не удалось изменить сортировку", String(error), false);
+  }
+}
+
+async function toggleFavorite(productId) {
+  if (!invoke || !productId) return;
+  try {
+    const cards = await invoke("toggle_favorite", { productId });
+    setCards(cards || [], { resetFilters: false });
+  } catch (error) {
+    setStatus("Не удалось обновить избранное", String(error), false);
+  }
+}
+
+async function openProductDetails(productId) {
+  const card = allCards.find(item => item.productId === productId);
+  if (!card) return;
+  setProductDetails(invoke ? null : buildCardDetails(card));
+  openModal("productModal");
+  if (!invoke) return;
+  try {
+    setProductDetails(await invoke("load_product_details", { productId }));
+  } catch (error) {
+    closeModal("productModal");
+    setStatus("Не удалось загрузить описание игры", String(error), false);
+  }
+}
+
+async function loadParentalStatus() {
+  if (!invoke) return;
+  try {
+    parentalStatus = await invoke("get_parental_status");
+    render(allCards);
+  } catch (error) {
+    // Without the status the button is hidden; the backend still filters.
+  }
+}
+
+async function unlockParentalMode(pin) {
+  if (!invoke) return;
+  try {
+    const cards = await invoke("unlock_parental_mode", { pin });
+    closeModal("parentalModal");
+    parentalStatus = { ...parentalStatus, active: false };
+    setCards(cards || [], { resetFilters: false });
+  } catch (error) {
+    closeModal("parentalModal");
+    setStatus("Не удалось отключить родительский контроль", String(error), false);
+  }
+}
+
+async function lockParentalMode() {
+  if (!invoke) return;
+  try {
+    const cards = await invoke("lock_parental_mode");
+    parentalStatus = { ...parentalStatus, active: true };
+    setCards(cards || [], { resetFilters: false });
+  } catch (error) {
+    setStatus("Не удалось включить родительский контроль", String(error), false);
+  }
+}
+
+function openExitPrompt() {
+  exitPin.value = "";
+  exitError.textContent = "";
+  openModal("exitModal");
+  exitPin.focus();
+}
+
+async function exitLauncher(pin) {
+  if (!invoke) return;
+  try {
+    await invoke("exit_launcher", { pin });
+  } catch (error) {
+    exitPin.value = "";
+    exitError.textContent = String(error);
+  }
+}
+
+async function exportDiagnostics() {
+  if (!invoke) return;
+  try {
+    const path = await invoke("export_diagnostics");
+    setStatus("Диагностика сохранена", path);
+  } catch (error) {
+    setStatus("Не удалось сохранить диагностику", String(error), true);
+  }
 }
 
 async function loadStationDetails() {
@@ -652,12 +1037,29 @@ async function loadStationDetails() {
   }
 }
 
-function formatProgressLabel(payload) {
-  const text = payload.text || "Загрузка…";
-  if (typeof payload.current === "number" && typeof payload.total === "number" && payload.total > 0) {
-    return ` — ${text} ${payload.current}/${payload.total}`;
+async function loadStations() {
+  if (!invoke) return;
+  try {
+    const list = await invoke("list_stations");
+    stations = Array.isArray(list) ? list : [];
+    render(allCards);
+  } catch (error) {
+    // Station switching is optional; keep the launcher usable without it.
+  }
+}
+
+async function selectStation(uuid) {
+  if (!invoke || !uuid) return;
+  closeModal("stationsModal");
+  const current = stations.find(station => station.isSelected);
+  if (current && current.uuid === uuid) return;
+  try {
+    await invoke("select_station", { uuid });
+    stations = stations.map(station => ({ ...station, isSelected: station.uuid === uuid }));
+    await loadCards();
+  } catch (error) {
+    setStatus("Не удалось выбрать станцию", String(error), false);
   }
-  return ` — ${text}`;
 }
 
 function openModal(id) {
@@ -679,12 +1081,14 @@ function closeModal(id) {
     document.body.classList.remove("is-modal-open");
     document.body.style.paddingRight = "";
   }
+  renderPendingCards();
 }
 
 function setStatus(text, sub = "", showRetry = false) {
   statusText.textContent = text || "";
   statusSub.textContent = sub || "";
   retryBtn.style.display = showRetry ? "inline-flex" : "none";
+  diagnosticsBtn.style.display = showRetry && invoke ? "inline-flex" : "none";
   openModal("status");
 }
 
@@ -694,21 +1098,65 @@ function clearStatus() {
 
 function handleStatusEvent(payload) {
   if (!payload) return;
+  // The `load_cards` result itself reports success or the error.
+  if (payload.phase === STATUS_PHASES.FAILED) return;
   if (loadingActive) {
-    setProgressLabel(formatProgressLabel(payload));
+    setProgressLabel(` — ${formatStatusProgress(payload)}`);
     return;
   }
-  const { text, current, total } = payload;
-  if (typeof current === "number" && typeof total === "number" && total > 0) {
-    setStatus(text || "Загрузка…", `Получено ${current}/${total}`);
+  if (payload.phase === STATUS_PHASES.DONE) {
+    clearStatus();
     return;
   }
-  setStatus(text || "Загрузка…");
+  setStatus(formatStatusProgress(payload));
+}
+
+function formatLaunchFailure(payload) {
+  const parts = [];
+  if (typeof payload.exitCode === "number") {
+    parts.push(`Код выхода: ${payload.exitCode}`);
+  }
+  const tail = String(payload.stderrTail || "").trim();
+  if (tail) {
+    parts.push(tail);
+  }
+  return parts.join("\n");
+}
+
+function handleLaunchFailed(payload) {
+  if (!payload) return;
+  hideLaunchOverlay(true);
+  setStatus("Игра завершилась сразу после запуска", formatLaunchFailure(payload), false);
+}
+
+function handleCardsChanged(diff) {
+  if (loadingActive) return;
+  allCards = applyCardsDiff(allCards, diff);
+  cardsRenderPending = true;
+  renderPendingCards();
+}
+
+function subscribeBackendEvents() {
+  listen("status", (event) => handleStatusEvent(event.payload));
+  listen("launch_failed", (event) => handleLaunchFailed(event.payload));
+  listen("launcher_restored", () => hideLaunchOverlay(true));
+  listen("cards_changed", (event) => handleCardsChanged(event.payload));
+  listen("exit_requested", () => openExitPrompt());
+  listen("station_changed", () => {
+    if (loadingActive) {
+      stationChangePending = true;
+    } else {
+      loadCards();
+    }
+  });
 }
 
 function loadCards() {
   lastLoadPromise = (async () => {
     loadStationDetails();
+    loadStations();
+    loadSortMode();
+    loadParentalStatus();
     loadingActive = true;
     clearStatus();
     setProgressLabel(" — Загрузка…");
@@ -725,6 +1173,10 @@ function loadCards() {
       setProgressLabel("");
       setCards([fallbackDesktopCard]);
     }
+    if (stationChangePending) {
+      stationChangePending = false;
+      await loadCards();
+    }
   })();
   return lastLoadPromise;
 }
@@ -755,7 +1207,7 @@ function initTauri(options = { load: true }) {
   const api = resolveTauriApi();
   if (api.listen && !listen) {
     listen = api.listen;
-    listen("status", (event) => handleStatusEvent(event.payload));
+    subscribeBackendEvents();
   }
   if (api.invoke && !invoke) {
     invoke = api.invoke;
@@ -822,7 +1274,7 @@ window.__resetLauncher = () => {
     const api = resolveTauriApi();
     if (api.listen) {
       listen = api.listen;
-      listen("status", (event) => handleStatusEvent(event.payload));
+      subscribeBackendEvents();
     }
     if (api.invoke) {
       invoke = api.invoke;
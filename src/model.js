@@ -27,8 +27,11 @@ export function buildLaunchParams(details = {}) {
   return { exePath, workDir, args };
 }
 
+// "No selection" for every dropdown filter and the sort dropdown.
+export const FILTER_ANY = "any";
+
 export const LICENSE_FILTERS = Object.freeze({
-  ANY: "any",
+  ANY: FILTER_ANY,
   FREE: "free",
   PAID: "paid"
 });
@@ -50,7 +53,12 @@ export function buildCards(enabledProducts = [], productMap = new Map()) {
       imageUrl,
       alt,
       requiredAccount,
-      isFree
+      isFree,
+      publisher: meta.publisher || "",
+      developer: meta.developer || "",
+      ageGroup: meta.ageGroup || "",
+      licenseType: meta.licenseType || "",
+      pagePicture: meta.pagePicture || ""
     };
   });
 }
@@ -58,6 +66,8 @@ export function buildCards(enabledProducts = [], productMap = new Map()) {
 export function buildCardFilterOptions(cards = []) {
   const games = new Set();
   const accounts = new Set();
+  const publishers = new Set();
+  const ageGroups = new Set();
 
   for (const card of cards) {
     const title = String(card?.title || "").trim();
@@ -69,11 +79,23 @@ export function buildCardFilterOptions(cards = []) {
     if (account) {
       accounts.add(account);
     }
+
+    const publisher = String(card?.publisher || "").trim();
+    if (publisher) {
+      publishers.add(publisher);
+    }
+
+    const ageGroup = String(card?.ageGroup || "").trim();
+    if (ageGroup) {
+      ageGroups.add(ageGroup);
+    }
   }
 
   return {
     games: [...games].sort(compareDisplayText),
-    accounts: [...accounts].sort(compareDisplayText)
+    accounts: [...accounts].sort(compareDisplayText),
+    publishers: [...publishers].sort(compareDisplayText),
+    ageGroups: [...ageGroups].sort(compareDisplayText)
   };
 }
 
@@ -83,10 +105,14 @@ export function applyCardFilters(cards = [], filters = {}) {
     : new Set();
   const selectedLicense = String(filters.license || LICENSE_FILTERS.ANY);
   const selectedAccount = String(filters.account || "").trim();
+  const selectedPublisher = String(filters.publisher || "").trim();
+  const selectedAgeGroup = String(filters.ageGroup || "").trim();
 
   return cards.filter(card => {
     const title = String(card?.title || "").trim();
     const account = String(card?.requiredAccount || "").trim();
+    const publisher = String(card?.publisher || "").trim();
+    const ageGroup = String(card?.ageGroup || "").trim();
     const isFree = card?.isFree === true;
 
     if (selectedGames.size > 0 && !selectedGames.has(title)) {
@@ -101,7 +127,15 @@ export function applyCardFilters(cards = [], filters = {}) {
       return false;
     }
 
-    if (selectedAccount && selectedAccount !== LICENSE_FILTERS.ANY && account !== selectedAccount) {
+    if (selectedAccount && selectedAccount !== FILTER_ANY && account !== selectedAccount) {
+      return false;
+    }
+
+    if (selectedPublisher && selectedPublisher !== FILTER_ANY && publisher !== selectedPublisher) {
+      return false;
+    }
+
+    if (selectedAgeGroup && selectedAgeGroup !== FILTER_ANY && ageGroup !== selectedAgeGroup) {
       return false;
     }
 
@@ -109,6 +143,43 @@ export function applyCardFilters(cards = [], filters = {}) {
   });
 }
 
+// `diff.order` is the backend display order (pinned, favorites, sort
+// mode); without it added cards go to the end.
+export function applyCardsDiff(cards = [], diff = {}) {
+  const removed = new Set(diff.removed || []);
+  const updated = new Map((diff.updated || []).map(card => [card.productId, card]));
+  const result = cards
+    .filter(card => !removed.has(card.productId))
+    .map(card => updated.get(card.productId) || card)
+    .concat(diff.added || []);
+  if (!Array.isArray(diff.order)) {
+    return result;
+  }
+  const position = new Map(diff.order.map((productId, index) => [productId, index]));
+  const rank = card => position.get(card.productId) ?? diff.order.length;
+  return result.sort((left, right) => rank(left) - rank(right));
+}
+
+export const STATUS_PHASES = {
+  DONE: "done",
+  FAILED: "failed"
+};
+
+// Backend `status` payload as "text 3/40 · 35%". `text` is already in the
+// backend locale; payloads without `percent` come from older backends.
+export function formatStatusProgress(payload = {}) {
+  const parts = [payload.text || "Загрузка…"];
+  const { current, total, percent } = payload;
+  if (typeof current === "number" && typeof total === "number" && total > 0) {
+    parts.push(`${current}/${total}`);
+  }
+  let label = parts.join(" ");
+  if (typeof percent === "number") {
+    label += ` · ${Math.round(percent)}%`;
+  }
+  return label;
+}
+
 function compareDisplayText(left, right) {
   return String(left).localeCompare(String(right), "ru", {
     sensitivity: "base",
@@ -2,12 +2,15 @@ import test from "node:test";
 import assert from "node:assert/strict";
 import {
   applyCardFilters,
+  applyCardsDiff,
   filterEnabledAvailable,
   buildProductMap,
   buildLaunchParams,
   buildCards,
   buildFallbackDesktopCard,
   buildCardFilterOptions,
+  formatStatusProgress,
+  FILTER_ANY,
   LICENSE_FILTERS
 } from "../src/model.js";
 import fs from "node:fs";
@@ -58,13 +61,17 @@ test("buildCards trims alt to 100 chars and maps badges", () => {
     descriptionRu: longText,
     requiredAccount: "Steam",
     noLicenseRequred: true,
-    cardPicture: "https://example.com/a.jpg"
+    cardPicture: "https://example.com/a.jpg",
+    publisher: "Valve",
+    ageGroup: "16+"
   }]);
   const cards = buildCards(enabled, map);
   assert.equal(cards[0].title, "Game Name");
   assert.equal(cards[0].alt.length, 100);
   assert.equal(cards[0].requiredAccount, "Steam");
   assert.equal(cards[0].isFree, true);
+  assert.equal(cards[0].publisher, "Valve");
+  assert.equal(cards[0].ageGroup, "16+");
 });
 
 test("buildLaunchParams prefers overrides", () => {
@@ -124,12 +131,12 @@ test("applyCardFilters filters by game, license and account", () => {
   ];
 
   assert.deepEqual(
-    applyCardFilters(cards, { games: ["Rust"], license: LICENSE_FILTERS.ANY, account: LICENSE_FILTERS.ANY }),
+    applyCardFilters(cards, { games: ["Rust"], license: LICENSE_FILTERS.ANY, account: FILTER_ANY }),
     [{ title: "Rust", requiredAccount: "Steam", isFree: false }]
   );
 
   assert.deepEqual(
-    applyCardFilters(cards, { games: [], license: LICENSE_FILTERS.FREE, account: LICENSE_FILTERS.ANY }),
+    applyCardFilters(cards, { games: [], license: LICENSE_FILTERS.FREE, account: FILTER_ANY }),
     [
       { title: "7 Days to Die", requiredAccount: "Epic Games", isFree: true },
       { title: "Desktop", requiredAccount: "", isFree: true }
@@ -141,3 +148,66 @@ test("applyCardFilters filters by game, license and account", () => {
     [{ title: "Rust", requiredAccount: "Steam", isFree: false }]
   );
 });
+
+test("buildCardFilterOptions and applyCardFilters handle publisher and age group", () => {
+  const cards = [
+    { title: "Dota 2", publisher: "Valve", ageGroup: "12+" },
+    { title: "Counter-Strike 2", publisher: "Valve", ageGroup: "16+" },
+    { title: "Desktop", publisher: "", ageGroup: "" }
+  ];
+
+  const options = buildCardFilterOptions(cards);
+  assert.deepEqual(options.publishers, ["Valve"]);
+  assert.deepEqual(options.ageGroups, ["12+", "16+"]);
+
+  assert.deepEqual(
+    applyCardFilters(cards, { games: [], publisher: "Valve", ageGroup: FILTER_ANY }).map(card => card.title),
+    ["Dota 2", "Counter-Strike 2"]
+  );
+  assert.deepEqual(
+    applyCardFilters(cards, { games: [], publisher: FILTER_ANY, ageGroup: "16+" }).map(card => card.title),
+    ["Counter-Strike 2"]
+  );
+});
+
+test("applyCardsDiff removes, replaces and appends cards", () => {
+  const cards = [
+    { productId: "a", title: "A" },
+    { productId: "b", title: "B" },
+    { productId: "c", title: "C" }
+  ];
+  const result = applyCardsDiff(cards, {
+    added: [{ productId: "d", title: "D" }],
+    removed: ["c"],
+    updated: [{ productId: "b", title: "B2" }]
+  });
+  assert.deepEqual(result.map(card => card.title), ["A", "B2", "D"]);
+  assert.equal(cards.length, 3);
+});
+
+test("applyCardsDiff places added cards in the backend order", () => {
+  const cards = [
+    { productId: "pinned", title: "Pinned" },
+    { productId: "b", title: "B" }
+  ];
+  const result = applyCardsDiff(cards, {
+    added: [{ productId: "a", title: "A" }],
+    removed: [],
+    updated: [],
+    order: ["pinned", "a", "b"]
+  });
+  assert.deepEqual(result.map(card => card.productId), ["pinned", "a", "b"]);
+});
+
+test("formatStatusProgress shows counts and overall percent", () => {
+  assert.equal(
+    formatStatusProgress({ text: "Загружаем ресурсы…", phase: "resources", percent: 65, current: 20, total: 40 }),
+    "Загружаем ресурсы… 20/40 · 65%"
+  );
+  assert.equal(
+    formatStatusProgress({ text: "Загружаем каталог игр…", phase: "catalog", percent: 15, current: null, total: null }),
+    "Загружаем каталог игр… · 15%"
+  );
+  assert.equal(formatStatusProgress({ text: "Загружаем ресурсы…", current: 1, total: 2 }), "Загружаем ресурсы… 1/2");
+  assert.equal(formatStatusProgress({}), "Загрузка…");
+});
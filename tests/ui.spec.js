@@ -15,6 +15,8 @@ async function addTauriStub(page, options = {}) {
     loadError = null,
     launchError = null,
     loadDelayMs = 0,
+    stations = null,
+    productDetails = null,
     stationDetails = {
       name: "Тестовый сервер",
       description: "<p>Описание</p>",
@@ -25,15 +27,20 @@ async function addTauriStub(page, options = {}) {
       }
     }
   } = options;
-  await page.addInitScript(({ cards, loadError, launchError, loadDelayMs, stationDetails }) => {
+  await page.addInitScript(({ cards, loadError, launchError, loadDelayMs, stations, productDetails, stationDetails }) => {
     window.__TAURI_TEST_DISABLE_AUTO_INIT = true;
     window.__invokeCalls = [];
     window.__statusCallback = null;
+    window.__eventCallbacks = {};
     window.__emitStatus = (payload) => {
       if (window.__statusCallback) {
         window.__statusCallback({ payload });
       }
     };
+    window.__emitEvent = (event, payload) => {
+      const cb = window.__eventCallbacks[event];
+      if (cb) cb({ payload });
+    };
     window.__TAURI__ = {
       core: {
         invoke: (cmd, args) => {
@@ -48,13 +55,28 @@ async function addTauriStub(page, options = {}) {
           if (cmd === "load_station_details") {
             return Promise.resolve(stationDetails);
           }
+          if (cmd === "load_product_details" && productDetails) {
+            return Promise.resolve(productDetails);
+          }
           if (cmd === "open_external_url") {
             return Promise.resolve(null);
           }
+          if (cmd === "list_stations" && stations) {
+            return Promise.resolve(stations);
+          }
+          if (cmd === "select_station" && stations) {
+            return Promise.resolve(null);
+          }
           if (cmd === "launch_game") {
             if (launchError) return Promise.reject(new Error(launchError));
             return Promise.resolve(null);
           }
+          if (cmd === "export_diagnostics") {
+            return Promise.resolve("C:\\Drova\\diagnostics\\drova-diagnostics.zip");
+          }
+          if (cmd === "exit_launcher") {
+            return args.pin === "1234" ? Promise.resolve(null) : Promise.reject("Неверный PIN");
+          }
           return Promise.reject(new Error(`Unknown command: ${cmd}`));
         }
       },
@@ -63,11 +85,12 @@ async function addTauriStub(page, options = {}) {
           if (event === "status") {
             window.__statusCallback = cb;
           }
+          window.__eventCallbacks[event] = cb;
           return Promise.resolve(() => {});
         }
       }
     };
-  }, { cards, loadError, launchError, loadDelayMs, stationDetails });
+  }, { cards, loadError, launchError, loadDelayMs, stations, productDetails, stationDetails });
 }
 
 function getContentType(filePath) {
@@ -188,6 +211,130 @@ test("tauri mode renders cards from invoke", async ({ page }) => {
   await expect(page.locator(".gameList__item")).toHaveCount(2);
 });
 
+test("status events show phase progress while loading", async ({ page }) => {
+  await addTauriStub(page, { loadDelayMs: 2000 });
+  await page.goto(`${baseUrl}/index.html`);
+  await page.waitForFunction(() => typeof window.__resetLauncher === "function");
+  await page.evaluate(() => {
+    window.__resetLauncher();
+  });
+  await page.evaluate(() =>
+    window.__emitStatus({
+      text: "Загружаем ресурсы…",
+      phase: "resources",
+      percent: 65,
+      error: false,
+      failedPhase: null,
+      current: 20,
+      total: 40
+    })
+  );
+  await expect(page.locator("#progressText")).toHaveText(" — Загружаем ресурсы… 20/40 · 65%");
+  await expect(page.locator("#status")).toHaveClass(/is-hidden/);
+});
+
+test("details button opens game page without launching", async ({ page }) => {
+  await addTauriStub(page, {
+    cards: [
+      { productId: "a", title: "A", imageUrl: "", alt: "Коротко", requiredAccount: "Steam", isFree: false }
+    ],
+    productDetails: {
+      productId: "a",
+      title: "A",
+      descriptionRu: "Полное описание игры",
+      descriptionEn: "Full description",
+      pagePicture: "",
+      publisher: "Valve",
+      developer: "Valve",
+      ageGroup: "16+",
+      licenseType: "",
+      requiredAccount: "Steam",
+      isFree: false,
+      canLaunch: true,
+      launchError: null
+    }
+  });
+  await page.goto(`${baseUrl}/index.html`);
+  await page.waitForFunction(() => typeof window.__resetLauncher === "function");
+  await page.evaluate(() => window.__resetLauncher());
+  const card = page.locator(".gameList__item").first();
+  await card.hover();
+  await card.locator("[data-details-open]").click();
+
+  await expect(page.locator("#productModal")).not.toHaveClass(/is-hidden/);
+  await expect(page.locator("#productDetails")).toContainText("Полное описание игры");
+  await expect(page.locator("#productDetails")).toContainText("16+");
+  await expect(card).not.toHaveClass(/is-launching/);
+  const calls = await page.evaluate(() => window.__invokeCalls.map(call => call.cmd));
+  expect(calls).toContain("load_product_details");
+  expect(calls).not.toContain("launch_game");
+});
+
+test("cards_changed waits until the open game page is closed", async ({ page }) => {
+  await addTauriStub(page, {
+    cards: [
+      { productId: "a", title: "A", imageUrl: "", alt: "", requiredAccount: "Steam", isFree: false }
+    ],
+    productDetails: {
+      productId: "a",
+      title: "A",
+      descriptionRu: "Полное описание игры",
+      descriptionEn: "",
+      pagePicture: "",
+      publisher: "",
+      developer: "",
+      ageGroup: "",
+      licenseType: "",
+      requiredAccount: "Steam",
+      isFree: false,
+      canLaunch: true,
+      launchError: null
+    }
+  });
+  await page.goto(`${baseUrl}/index.html`);
+  await page.waitForFunction(() => typeof window.__resetLauncher === "function");
+  await page.evaluate(() => window.__resetLauncher());
+  const card = page.locator(".gameList__item").first();
+  await card.hover();
+  await card.locator("[data-details-open]").click();
+  await expect(page.locator("#productModal")).not.toHaveClass(/is-hidden/);
+
+  await page.evaluate(() => window.__emitEvent("cards_changed", {
+    added: [{ productId: "b", title: "B", imageUrl: "", alt: "", requiredAccount: "Steam", isFree: true }],
+    removed: [],
+    updated: [],
+    order: ["a", "b"]
+  }));
+  await expect(page.locator("#productModal")).not.toHaveClass(/is-hidden/);
+  await expect(page.locator("body")).toHaveClass(/is-modal-open/);
+  await expect(page.locator(".gameList__item")).toHaveCount(1);
+
+  await page.locator('[data-close="productModal"]').click();
+  await expect(page.locator(".gameList__item")).toHaveCount(2);
+  await expect(page.locator("body")).not.toHaveClass(/is-modal-open/);
+});
+
+test("kiosk close request asks for the owner PIN", async ({ page }) => {
+  await addTauriStub(page, {
+    cards: [{ productId: "a", title: "A", imageUrl: "", alt: "", requiredAccount: "", isFree: true }]
+  });
+  await page.goto(`${baseUrl}/index.html`);
+  await page.waitForFunction(() => typeof window.__resetLauncher === "function");
+  await page.evaluate(() => window.__resetLauncher());
+  await page.evaluate(() => window.__emitEvent("exit_requested"));
+
+  await expect(page.locator("#exitModal")).not.toHaveClass(/is-hidden/);
+  await page.fill("#exitPin", "0000");
+  await page.locator("#exitForm button[type=submit]").click();
+  await expect(page.locator("#exitError")).toHaveText("Неверный PIN");
+  await page.fill("#exitPin", "1234");
+  await page.locator("#exitForm button[type=submit]").click();
+  const pins = await page.evaluate(() =>
+    window.__invokeCalls.filter(call => call.cmd === "exit_launcher").map(call => call.args.pin)
+  );
+  expect(pins).toEqual(["0000", "1234"]);
+});
+
 test("tauri load_cards error shows retry and fallback", async ({ page }) => {
   await addTauriStub(page, { loadError: "fail" });
   await page.goto(`${baseUrl}/index.html`);
@@ -195,6 +342,10 @@ test("tauri load_cards error shows retry and fallback", async ({ page }) => {
   await page.evaluate(() => window.__resetLauncher());
   await expect(page.locator("#retryBtn")).toBeVisible();
   await expect(page.locator('.gameList__item[data-product-id="desktop"]')).toHaveCount(1);
+
+  await page.locator("#diagnosticsBtn").click();
+  await expect(page.locator("#statusText")).toHaveText("Диагностика сохранена");
+  await expect(page.locator("#statusSub")).toContainText("drova-diagnostics.zip");
 });
 
 test("tauri launch_game error clears launching and shows status", async ({ page }) => {
@@ -211,6 +362,43 @@ test("tauri launch_game error clears launching and shows status", async ({ page
   await expect(card).not.toHaveClass(/is-launching/);
 });
 
+test("station picker selects another station and reloads cards", async ({ page }) => {
+  await addTauriStub(page, {
+    cards: [{ productId: "a", title: "A", imageUrl: "", alt: "", requiredAccount: "", isFree: false }],
+    stations: [
+      { uuid: "uuid-1", name: "Первая", isLastServer: true, isSelected: true },
+      { uuid: "uuid-2", name: "Вторая", isLastServer: false, isSelected: false }
+    ]
+  });
+  await page.goto(`${baseUrl}/index.html`);
+  await page.waitForFunction(() => typeof window.__resetLauncher === "function");
+  await page.evaluate(() => window.__resetLauncher());
+
+  await page.locator("#openStations").click();
+  await expect(page.locator("#stationsModal")).not.toHaveClass(/is-hidden/);
+  await page.locator('[data-station-uuid="uuid-2"]').click();
+
+  await expect.poll(async () => page.evaluate(() =>
+    window.__invokeCalls.filter(call => call.cmd === "load_cards").length
+  )).toBe(2);
+  const calls = await page.evaluate(() => window.__invokeCalls);
+  expect(calls.some(call => call.cmd === "select_station" && call.args?.uuid === "uuid-2")).toBe(true);
+});
+
+test("station change during a load reloads cards afterwards", async ({ page }) => {
+  await addTauriStub(page, { loadDelayMs: 300 });
+  await page.goto(`${baseUrl}/index.html`);
+  await page.waitForFunction(() => typeof window.__resetLauncher === "function");
+  await page.evaluate(() => {
+    window.__resetLauncher();
+  });
+  await page.evaluate(() => window.__emitEvent("station_changed", { uuid: "uuid-2" }));
+
+  await expect.poll(async () => page.evaluate(() =>
+    window.__invokeCalls.filter(call => call.cmd === "load_cards").length
+  )).toBe(2);
+});
+
 test("desktop launch keeps overlay until dismissed", async ({ page }) => {
   await addTauriStub(page, {
     cards: [{ productId: "desktop", title: "Рабочий стол", imageUrl: "", alt: "", requiredAccount: "", isFree: true, isDesktop: true }]